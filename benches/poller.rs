@@ -0,0 +1,35 @@
+//! Compares polling through `Platform` with polling through `Poller`.
+//!
+//! Run with `cargo bench --bench poller`.
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod linux {
+    use criterion::{black_box, Criterion};
+    use systemstat::{Platform, Poller, System};
+
+    pub fn cpu_load(c: &mut Criterion) {
+        let mut group = c.benchmark_group("cpu_load");
+        let mut measurement = System::new().cpu_load().unwrap();
+        group.bench_function("platform", |b| b.iter(|| black_box(measurement.resample().unwrap())));
+        let mut poller = Poller::new().unwrap();
+        group.bench_function("poller", |b| b.iter(|| black_box(poller.cpu_load().unwrap().len())));
+        group.finish();
+    }
+
+    pub fn block_device_statistics(c: &mut Criterion) {
+        let mut group = c.benchmark_group("block_device_statistics");
+        let sys = System::new();
+        group.bench_function("platform", |b| b.iter(|| black_box(sys.block_device_statistics().unwrap())));
+        let mut poller = Poller::new().unwrap();
+        group.bench_function("poller", |b| b.iter(|| black_box(poller.block_device_statistics().unwrap().len())));
+        group.finish();
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+criterion::criterion_group!(benches, linux::cpu_load, linux::block_device_statistics);
+#[cfg(any(target_os = "linux", target_os = "android"))]
+criterion::criterion_main!(benches);
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn main() {}
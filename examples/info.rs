@@ -2,11 +2,13 @@ extern crate systemstat;
 
 use std::thread;
 use std::time::Duration;
-use systemstat::{System, Platform, saturating_sub_bytes};
+use systemstat::{System, Platform, DelayedMeasurement, saturating_sub_bytes};
 
 fn main() {
     let sys = System::new();
 
+    println!("Capabilities: {:?}", sys.capabilities());
+
     match sys.mounts() {
         Ok(mounts) => {
             println!("\nMounts:");
@@ -36,6 +38,23 @@ fn main() {
         Err(x) => println!("\nBlock statistics error: {}", x)
     }
 
+    match sys.disk_statistics_aggregate() {
+        Ok(total) => println!("\nAll physical disks: {} reads, {} writes", total.read_ios, total.write_ios),
+        Err(x) => println!("\nAll physical disks: error: {}", x)
+    }
+
+    match sys.disk_io_rates() {
+        Ok(rates) => {
+            println!("\nMeasuring disk I/O...");
+            thread::sleep(Duration::from_secs(1));
+            for (name, disk) in rates.done().unwrap() {
+                println!("{}: {:.1}% util, {:.1} r/s, {:.1} w/s, {:.1} rkB/s, {:.1} wkB/s, await {:.2} ms",
+                         name, disk.utilization, disk.reads, disk.writes, disk.read_kib, disk.write_kib, disk.await_ms);
+            }
+        }
+        Err(x) => println!("\nDisk I/O rates: error: {}", x)
+    }
+
     match sys.networks() {
         Ok(netifs) => {
             println!("\nNetworks:");
@@ -56,6 +75,17 @@ fn main() {
         Err(x) => println!("\nNetworks: error: {}", x)
     }
 
+    match sys.wireless() {
+        Ok(wireless) => {
+            println!("\nWireless interfaces:");
+            for (name, stats) in wireless {
+                println!("{}: quality {}, signal {:?} dBm, noise {:?} dBm, {} missed beacons",
+                         name, stats.link_quality, stats.signal_dbm, stats.noise_dbm, stats.missed_beacons);
+            }
+        }
+        Err(x) => println!("\nWireless: error: {}", x)
+    }
+
     match sys.battery_life() {
         Ok(battery) =>
             print!("\nBattery: {}%, {}h{}m remaining",
@@ -115,4 +145,81 @@ fn main() {
         Ok(stats) => println!("\nSystem socket statistics: {:?}", stats),
         Err(x) => println!("\nSystem socket statistics: error: {}", x)
     }
+
+    match sys.os_info() {
+        Ok(os) => println!("\nOS: {} {} {} on {} ({}), distribution: {:?}, virtualization: {:?}",
+                           os.kernel_name, os.kernel_release, os.machine, os.hostname, os.kernel_version,
+                           os.distribution, os.virtualization),
+        Err(x) => println!("\nOS: error: {}", x)
+    }
+
+    match sys.users() {
+        Ok(users) => {
+            println!("\nUsers:");
+            for user in users.iter() {
+                println!("{} on {} from {:?} since {} (pid {})",
+                         user.username, user.tty, user.host, user.login_time, user.pid);
+            }
+        }
+        Err(x) => println!("\nUsers: error: {}", x)
+    }
+
+    match sys.hardware_info() {
+        Ok(hw) => println!("\nHardware: {:?}", hw),
+        Err(x) => println!("\nHardware: error: {}", x)
+    }
+
+    match sys.kernel_limits() {
+        Ok(limits) => println!("\nKernel limits: {} / {} file handles, {} / {} PIDs, {} bits of entropy ({:?})",
+                               limits.file_handles, limits.file_handles_max, limits.pids, limits.pid_max,
+                               limits.entropy_avail, limits),
+        Err(x) => println!("\nKernel limits: error: {}", x)
+    }
+
+    match sys.interrupt_rates() {
+        Ok(rates) => {
+            println!("\nMeasuring interrupt rates...");
+            thread::sleep(Duration::from_secs(1));
+            for irq in rates.done().unwrap().iter().filter(|i| i.per_cpu.iter().any(|&r| r > 0.0)) {
+                println!("{:?} {} ({}): {:?}/s", irq.kind, irq.irq, irq.description, irq.per_cpu);
+            }
+        },
+        Err(x) => println!("\nInterrupt rates: error: {}", x)
+    }
+
+    match sys.cpu_availability() {
+        Ok(cpus) => println!("\nCPU availability: {} usable in parallel ({} online, affinity {:?}, quota {:?})",
+                             cpus.parallelism(), cpus.online.len(), cpus.affinity, cpus.quota),
+        Err(x) => println!("\nCPU availability: error: {}", x)
+    }
+
+    let pid = std::process::id();
+    match sys.process_fds(pid) {
+        Ok(fds) => println!("\nThis process: {} open fds ({:?})", fds.total(), fds),
+        Err(x) => println!("\nThis process: fds: error: {}", x)
+    }
+    match sys.process_io(pid) {
+        Ok(io) => println!("This process: read {}, written {}", io.read_chars, io.written_chars),
+        Err(x) => println!("This process: I/O: error: {}", x)
+    }
+    match sys.process_memory(pid) {
+        Ok(mem) => println!("This process: RSS {}, PSS {}, USS {}, swap {}", mem.rss, mem.pss, mem.uss, mem.swap),
+        Err(x) => println!("This process: memory: error: {}", x)
+    }
+    match sys.threads(pid) {
+        Ok(threads) => println!("This process: {} threads", threads.len()),
+        Err(x) => println!("This process: threads: error: {}", x)
+    }
+
+    match DelayedMeasurement::rates(move || sys.processes()) {
+        Ok(measurement) => {
+            println!("\nMeasuring process CPU usage...");
+            thread::sleep(Duration::from_secs(1));
+            let tree = measurement.done().unwrap();
+            for (user, usage) in tree.by_user() {
+                println!("{}: {} processes, {:.2} CPUs, RSS {}", user, usage.processes, usage.cpu, usage.rss);
+            }
+        },
+        Err(x) => println!("\nProcesses: error: {}", x)
+    }
 }
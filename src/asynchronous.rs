@@ -0,0 +1,305 @@
+//! This module provides an async facade over `Platform`, enabled by the `async` feature.
+//!
+//! All `Platform` methods block, mostly on file I/O, and `cpu_load` requires waiting between
+//! starting and finishing the measurement. Here, each call runs on a separate short-lived thread,
+//! so that awaiting it doesn't block the executor's worker threads. It works with any executor,
+//! nothing here depends on a particular runtime.
+//!
+//! ```no_run
+//! # async fn example() -> std::io::Result<()> {
+//! use std::time::Duration;
+//! use systemstat::asynchronous::AsyncSystem;
+//!
+//! let sys = AsyncSystem::new();
+//! let cpus = sys.cpu_load_over(Duration::from_secs(1)).await?;
+//! let mem = sys.memory().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::{io, path, thread};
+use crate::data::*;
+use crate::platform::{Platform, PlatformImpl};
+
+struct Shared<T> {
+    result: Option<io::Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// A future that resolves when a blocking call running on another thread returns.
+pub struct Blocking<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = io::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<T>> {
+        let mut shared = self.shared.lock().expect("lock should not be poisoned");
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Runs a blocking function on a new thread.
+pub fn unblock<T, F>(f: F) -> Blocking<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> io::Result<T> + Send + 'static,
+{
+    let shared = Arc::new(Mutex::new(Shared { result: None, waker: None }));
+    let thread_shared = shared.clone();
+    let spawned = thread::Builder::new()
+        .name("systemstat".to_string())
+        .spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f))
+                .unwrap_or_else(|_| Err(io::Error::other("systemstat call panicked")));
+            let mut shared = thread_shared.lock().expect("lock should not be poisoned");
+            shared.result = Some(result);
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        });
+    if let Err(err) = spawned {
+        shared.lock().expect("lock should not be poisoned").result = Some(Err(err));
+    }
+    Blocking { shared }
+}
+
+/// Finishes a `DelayedMeasurement` after `duration` has passed, without blocking the caller.
+pub fn done_after<T: Send + 'static>(measurement: DelayedMeasurement<T>, duration: Duration) -> Blocking<T> {
+    unblock(move || {
+        thread::sleep(duration);
+        measurement.done()
+    })
+}
+
+/// An async wrapper around a `Platform` (by default, `System`).
+/// See `Platform` for documentation of the methods.
+pub struct AsyncSystem<P = PlatformImpl> {
+    platform: Arc<P>,
+}
+
+impl<P> Clone for AsyncSystem<P> {
+    fn clone(&self) -> Self {
+        AsyncSystem { platform: self.platform.clone() }
+    }
+}
+
+impl AsyncSystem {
+    pub fn new() -> AsyncSystem {
+        AsyncSystem::from_platform(PlatformImpl::new())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub async fn users_from_file<Q: AsRef<path::Path>>(&self, path: Q) -> io::Result<Vec<UserSession>> {
+        let platform = self.platform.clone();
+        let path = path.as_ref().to_path_buf();
+        unblock(move || platform.users_from_file(&path)).await
+    }
+}
+
+impl Default for AsyncSystem {
+    fn default() -> AsyncSystem {
+        AsyncSystem::new()
+    }
+}
+
+macro_rules! unblock_methods {
+    ($($name:ident($($arg:ident: $arg_ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            pub async fn $name(&self, $($arg: $arg_ty),*) -> io::Result<$ret> {
+                let platform = self.platform.clone();
+                unblock(move || platform.$name($($arg),*)).await
+            }
+        )*
+    }
+}
+
+impl<P: Platform + Send + Sync + 'static> AsyncSystem<P> {
+    pub fn from_platform(platform: P) -> AsyncSystem<P> {
+        AsyncSystem { platform: Arc::new(platform) }
+    }
+
+    /// Measures the per-CPU load over `duration`.
+    pub async fn cpu_load_over(&self, duration: Duration) -> io::Result<Vec<CPULoad>> {
+        let platform = self.platform.clone();
+        unblock(move || {
+            let measurement = platform.cpu_load()?;
+            thread::sleep(duration);
+            measurement.done()
+        })
+        .await
+    }
+
+    /// Measures the load of all CPUs combined over `duration`.
+    pub async fn cpu_load_aggregate_over(&self, duration: Duration) -> io::Result<CPULoad> {
+        let platform = self.platform.clone();
+        unblock(move || {
+            let measurement = platform.cpu_load_aggregate()?;
+            thread::sleep(duration);
+            measurement.done()
+        })
+        .await
+    }
+
+    /// Measures interrupt rates over `duration`.
+    pub async fn interrupt_rates_over(&self, duration: Duration) -> io::Result<Vec<InterruptRate>> {
+        let platform = self.platform.clone();
+        unblock(move || {
+            let measurement = platform.interrupt_rates()?;
+            thread::sleep(duration);
+            measurement.done()
+        })
+        .await
+    }
+
+    /// Measures the per-thread CPU load of a process over `duration`.
+    pub async fn thread_load_over(&self, pid: u32, duration: Duration) -> io::Result<Vec<ThreadLoad>> {
+        let platform = self.platform.clone();
+        unblock(move || {
+            let measurement = platform.thread_load(pid)?;
+            thread::sleep(duration);
+            measurement.done()
+        })
+        .await
+    }
+
+    /// Measures the block device metrics over `duration`.
+    pub async fn disk_io_rates_over(&self, duration: Duration) -> io::Result<BTreeMap<String, DiskIoRates>> {
+        let platform = self.platform.clone();
+        unblock(move || {
+            let measurement = platform.disk_io_rates()?;
+            thread::sleep(duration);
+            measurement.done()
+        })
+        .await
+    }
+
+    unblock_methods! {
+        load_average() -> LoadAverage;
+        memory() -> Memory;
+        swap() -> Swap;
+        memory_and_swap() -> (Memory, Swap);
+        uptime() -> Duration;
+        boot_time() -> OffsetDateTime;
+        battery_life() -> BatteryLife;
+        on_ac_power() -> bool;
+        mounts() -> Vec<Filesystem>;
+        block_device_statistics() -> BTreeMap<String, BlockDeviceStats>;
+        networks() -> BTreeMap<String, Network>;
+        wireless() -> BTreeMap<String, WirelessStats>;
+        cpu_temp() -> f32;
+        socket_stats() -> SocketStats;
+        os_info() -> OsInfo;
+        users() -> Vec<UserSession>;
+        hardware_info() -> HardwareInfo;
+        kernel_limits() -> KernelLimits;
+        interrupts() -> Vec<Interrupt>;
+        cpu_availability() -> CpuAvailability;
+        processes() -> Vec<Process>;
+        threads(pid: u32) -> Vec<Thread>;
+        process_fds(pid: u32) -> ProcessFds;
+        process_io(pid: u32) -> ProcessIo;
+        process_memory(pid: u32) -> ProcessMemory;
+    }
+
+    pub async fn mount_at<Q: AsRef<path::Path>>(&self, path: Q) -> io::Result<Filesystem> {
+        let platform = self.platform.clone();
+        let path = path.as_ref().to_path_buf();
+        unblock(move || platform.mount_at(path)).await
+    }
+
+    pub async fn network_stats(&self, interface: &str) -> io::Result<NetworkStats> {
+        let platform = self.platform.clone();
+        let interface = interface.to_string();
+        unblock(move || platform.network_stats(&interface)).await
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.platform.capabilities()
+    }
+
+    pub async fn snapshot(&self, options: &SnapshotOptions) -> Snapshot {
+        let platform = self.platform.clone();
+        let options = options.clone();
+        unblock(move || Ok(platform.snapshot(&options)))
+            .await
+            .expect("snapshot should not fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+    use std::time::Instant;
+
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unblock() {
+        assert_eq!(block_on(unblock(|| Ok(42))).unwrap(), 42);
+        let err = block_on(unblock::<(), _>(|| panic!("oops"))).unwrap_err();
+        assert_eq!(err.to_string(), "systemstat call panicked");
+    }
+
+    #[test]
+    fn test_cpu_load_over() {
+        let sys = AsyncSystem::new();
+        let start = Instant::now();
+        let future = sys.cpu_load_over(Duration::from_millis(100));
+        // Nothing runs until polled, and polling doesn't block
+        assert!(start.elapsed() < Duration::from_millis(100));
+        let cpus = block_on(future);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        if sys.capabilities().supports(Capability::CpuLoad) {
+            assert!(!cpus.unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_network_stats() {
+        let sys = AsyncSystem::new();
+        let blocking = PlatformImpl::new();
+        let names = |networks: &BTreeMap<String, Network>| networks.keys().cloned().collect::<Vec<_>>();
+        let networks = block_on(sys.networks());
+        assert_eq!(networks.as_ref().map(names).ok(), blocking.networks().as_ref().map(names).ok());
+        for name in networks.unwrap_or_default().keys() {
+            let before = blocking.network_stats(name);
+            let stats = block_on(sys.network_stats(name));
+            assert_eq!(stats.is_ok(), before.is_ok(), "{}", name);
+            if let (Ok(before), Ok(stats)) = (before, stats) {
+                assert!(stats.rx_packets >= before.rx_packets, "{}", name);
+            }
+        }
+    }
+}
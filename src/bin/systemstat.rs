@@ -0,0 +1,410 @@
+//! A command-line tool for quickly looking at the state of a system.
+//!
+//! Built when the `cli` feature is enabled.
+
+extern crate systemstat;
+extern crate serde_json;
+
+use std::{env, process, thread};
+use std::time::Duration;
+use serde_json::json;
+use systemstat::*;
+
+const USAGE: &str = "Usage: systemstat [--json] [--interval SECONDS] [--count N] [cpu|mem|disks|net|battery|all]
+
+Commands:
+    cpu        Per-CPU load, measured over the interval (1 second by default)
+    mem        Memory and swap usage
+    disks      Mounted filesystems
+    net        Network interfaces and their statistics
+    battery    Battery and AC power status
+    all        Everything above and more (default)
+
+Options:
+    --json               Print one JSON object per sample, as in systemstat::schema
+    --interval SECONDS   Time between samples
+    --count N            Number of samples (default: 1, or unlimited with --interval)
+    -h, --help           Print this message";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Cpu,
+    Mem,
+    Disks,
+    Net,
+    Battery,
+    All,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    command: Command,
+    json: bool,
+    interval: Option<Duration>,
+    count: Option<u64>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut opts = Options {
+        command: Command::All,
+        json: false,
+        interval: None,
+        count: None,
+    };
+    let mut command = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => opts.json = true,
+            "--interval" | "-i" => {
+                let value = args.next().ok_or("--interval requires a value")?;
+                let secs = value.parse::<f64>().ok().filter(|s| s.is_finite() && *s > 0.0)
+                    .ok_or_else(|| format!("invalid interval: {}", value))?;
+                opts.interval = Some(Duration::from_secs_f64(secs));
+            }
+            "--count" | "-c" => {
+                let value = args.next().ok_or("--count requires a value")?;
+                opts.count = Some(value.parse().map_err(|_| format!("invalid count: {}", value))?);
+            }
+            "-h" | "--help" => return Err(String::new()),
+            cmd if command.is_none() && !cmd.starts_with('-') => {
+                command = Some(match cmd {
+                    "cpu" => Command::Cpu,
+                    "mem" => Command::Mem,
+                    "disks" => Command::Disks,
+                    "net" => Command::Net,
+                    "battery" => Command::Battery,
+                    "all" => Command::All,
+                    _ => return Err(format!("unknown command: {}", cmd)),
+                });
+            }
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    if let Some(command) = command {
+        opts.command = command;
+    }
+    if opts.interval.is_none() && opts.count.is_none() {
+        opts.count = Some(1);
+    }
+    Ok(opts)
+}
+
+fn cpu_load(sys: &System, duration: Duration) -> SnapshotResult<Vec<CPULoad>> {
+    let measurement = sys.cpu_load()?;
+    thread::sleep(duration);
+    Ok(measurement.done()?)
+}
+
+fn network_stats(sys: &System, networks: &SnapshotResult<BTreeMap<String, Network>>) -> BTreeMap<String, SnapshotResult<NetworkStats>> {
+    match networks {
+        Ok(networks) => networks
+            .keys()
+            .map(|name| (name.clone(), sys.network_stats(name).map_err(SnapshotError::from)))
+            .collect(),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn percent(x: f32) -> String {
+    format!("{:.1}%", x * 100.0)
+}
+
+fn print_error(section: &str, err: &SnapshotError) {
+    println!("{}: {}", section, err.message);
+}
+
+fn print_cpu(cpus: &SnapshotResult<Vec<CPULoad>>) {
+    match cpus {
+        Ok(cpus) => {
+            println!("{:<6} {:>7} {:>7} {:>7} {:>7} {:>7}", "CPU", "USER", "NICE", "SYSTEM", "INTR", "IDLE");
+            for (i, cpu) in cpus.iter().enumerate() {
+                println!("{:<6} {:>7} {:>7} {:>7} {:>7} {:>7}", i,
+                         percent(cpu.user), percent(cpu.nice), percent(cpu.system),
+                         percent(cpu.interrupt), percent(cpu.idle));
+            }
+        }
+        Err(err) => print_error("CPU load", err),
+    }
+}
+
+fn print_mem(mem: &SnapshotResult<Memory>, swap: &SnapshotResult<Swap>) {
+    println!("{:<6} {:>12} {:>12} {:>12}", "", "TOTAL", "USED", "FREE");
+    match mem {
+        Ok(mem) => println!("{:<6} {:>12} {:>12} {:>12}", "Mem",
+                            mem.total, saturating_sub_bytes(mem.total, mem.free), mem.free),
+        Err(err) => print_error("Mem", err),
+    }
+    match swap {
+        Ok(swap) => println!("{:<6} {:>12} {:>12} {:>12}", "Swap",
+                             swap.total, saturating_sub_bytes(swap.total, swap.free), swap.free),
+        Err(err) => print_error("Swap", err),
+    }
+}
+
+fn print_disks(mounts: &SnapshotResult<Vec<Filesystem>>) {
+    match mounts {
+        Ok(mounts) => {
+            println!("{:<24} {:<8} {:>12} {:>12} {:>12} {:>6}  MOUNTED ON", "FILESYSTEM", "TYPE", "SIZE", "USED", "AVAIL", "USE%");
+            for fs in mounts.iter().filter(|fs| fs.total.as_u64() > 0) {
+                let used = saturating_sub_bytes(fs.total, fs.free);
+                println!("{:<24} {:<8} {:>12} {:>12} {:>12} {:>6}  {}",
+                         fs.fs_mounted_from, fs.fs_type, fs.total, used, fs.avail,
+                         percent(used.as_u64() as f32 / fs.total.as_u64() as f32), fs.fs_mounted_on);
+            }
+        }
+        Err(err) => print_error("Disks", err),
+    }
+}
+
+fn print_net(networks: &SnapshotResult<BTreeMap<String, Network>>, stats: &BTreeMap<String, SnapshotResult<NetworkStats>>) {
+    match networks {
+        Ok(networks) => {
+            println!("{:<16} {:>12} {:>12} {:>10} {:>10} {:>8} {:>8}  ADDRESSES",
+                     "INTERFACE", "RX", "TX", "RX PKTS", "TX PKTS", "RX ERRS", "TX ERRS");
+            for network in networks.values() {
+                let addrs = network.addrs.iter()
+                    .filter_map(|a| match a.addr {
+                        IpAddr::V4(ip) => Some(ip.to_string()),
+                        IpAddr::V6(ip) => Some(ip.to_string()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                match stats.get(&network.name) {
+                    Some(Ok(s)) => println!("{:<16} {:>12} {:>12} {:>10} {:>10} {:>8} {:>8}  {}",
+                                            network.name, s.rx_bytes, s.tx_bytes, s.rx_packets,
+                                            s.tx_packets, s.rx_errors, s.tx_errors, addrs),
+                    _ => println!("{:<16} {:>12} {:>12} {:>10} {:>10} {:>8} {:>8}  {}",
+                                  network.name, "-", "-", "-", "-", "-", "-", addrs),
+                }
+            }
+        }
+        Err(err) => print_error("Networks", err),
+    }
+}
+
+fn print_battery(battery: &SnapshotResult<BatteryLife>, on_ac: &SnapshotResult<bool>) {
+    match battery {
+        Ok(battery) => {
+            let secs = battery.remaining_time.as_secs();
+            println!("Battery: {}, {}h{:02}m remaining", percent(battery.remaining_capacity), secs / 3600, secs / 60 % 60);
+        }
+        Err(err) => print_error("Battery", err),
+    }
+    match on_ac {
+        Ok(on_ac) => println!("AC power: {}", if *on_ac { "yes" } else { "no" }),
+        Err(err) => print_error("AC power", err),
+    }
+}
+
+fn print_all(snapshot: &Snapshot) {
+    if let Ok(info) = &snapshot.os_info {
+        println!("{} ({} {})", info.hostname, info.kernel_name, info.kernel_release);
+    }
+    if let Ok(uptime) = &snapshot.uptime {
+        let secs = uptime.as_secs();
+        print!("Up {}d {}h{:02}m", secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+        if let Ok(load) = &snapshot.load_average {
+            print!(", load average: {:.2} {:.2} {:.2}", load.one, load.five, load.fifteen);
+        }
+        println!();
+    }
+    if let Some(cpus) = &snapshot.cpu_load {
+        println!();
+        print_cpu(cpus);
+    }
+    println!();
+    print_mem(&snapshot.memory, &snapshot.swap);
+    println!();
+    print_disks(&snapshot.mounts);
+    println!();
+    print_net(&snapshot.networks, &snapshot.network_stats);
+    println!();
+    print_battery(&snapshot.battery_life, &snapshot.on_ac_power);
+}
+
+/// What one run of a command collected.
+enum Sample {
+    Cpu(SnapshotResult<Vec<CPULoad>>),
+    Mem(SnapshotResult<Memory>, SnapshotResult<Swap>),
+    Disks(SnapshotResult<Vec<Filesystem>>),
+    Net(SnapshotResult<BTreeMap<String, Network>>, BTreeMap<String, SnapshotResult<NetworkStats>>),
+    Battery(SnapshotResult<BatteryLife>, SnapshotResult<bool>),
+    All(Box<Snapshot>),
+}
+
+fn collect(sys: &System, command: Command, duration: Duration) -> Sample {
+    match command {
+        Command::Cpu => Sample::Cpu(cpu_load(sys, duration)),
+        Command::Mem => Sample::Mem(sys.memory().map_err(SnapshotError::from), sys.swap().map_err(SnapshotError::from)),
+        Command::Disks => Sample::Disks(sys.mounts().map_err(SnapshotError::from)),
+        Command::Net => {
+            let networks = sys.networks().map_err(SnapshotError::from);
+            let stats = network_stats(sys, &networks);
+            Sample::Net(networks, stats)
+        }
+        Command::Battery => Sample::Battery(sys.battery_life().map_err(SnapshotError::from), sys.on_ac_power().map_err(SnapshotError::from)),
+        Command::All => Sample::All(Box::new(sys.snapshot(&SnapshotOptions {
+            cpu_load_duration: Some(duration),
+            ..SnapshotOptions::default()
+        }))),
+    }
+}
+
+fn print(sample: &Sample) {
+    match sample {
+        Sample::Cpu(cpus) => print_cpu(cpus),
+        Sample::Mem(mem, swap) => print_mem(mem, swap),
+        Sample::Disks(mounts) => print_disks(mounts),
+        Sample::Net(networks, stats) => print_net(networks, stats),
+        Sample::Battery(battery, on_ac) => print_battery(battery, on_ac),
+        Sample::All(snapshot) => print_all(snapshot),
+    }
+}
+
+/// Uses the types and field names of `schema::Snapshot`, so that every command's output is a
+/// subset of what `all` prints.
+fn to_json(timestamp: &OffsetDateTime, sample: &Sample) -> serde_json::Result<serde_json::Value> {
+    use systemstat::schema::Metric;
+
+    let metrics = match sample {
+        Sample::Cpu(cpus) => json!({
+            "cpu_load": Metric::from_result(cpus, |cpus| cpus.iter().map(schema::CpuLoad::from).collect::<Vec<_>>()),
+        }),
+        Sample::Mem(mem, swap) => json!({
+            "memory": Metric::from_result(mem, |m| schema::Memory::from(m)),
+            "swap": Metric::from_result(swap, |m| schema::Memory::from(m)),
+        }),
+        Sample::Disks(mounts) => json!({
+            "mounts": Metric::from_result(mounts, |mounts| mounts.iter().map(schema::Filesystem::from).collect::<Vec<_>>()),
+        }),
+        Sample::Net(networks, stats) => json!({
+            "networks": Metric::from_result(networks, |networks| networks.iter()
+                .map(|(name, n)| (name.clone(), n.addrs.iter().map(schema::NetworkAddrs::from).collect::<Vec<_>>()))
+                .collect::<BTreeMap<_, _>>()),
+            "network_stats": stats.iter()
+                .map(|(name, s)| (name.clone(), Metric::from_result(s, |s| schema::NetworkStats::from(s))))
+                .collect::<BTreeMap<_, _>>(),
+        }),
+        Sample::Battery(battery, on_ac) => json!({
+            "battery_life": Metric::from_result(battery, |b| schema::BatteryLife::from(b)),
+            "on_ac_power": Metric::from_result(on_ac, |x| *x),
+        }),
+        Sample::All(snapshot) => return serde_json::to_value(schema::Snapshot::from(&**snapshot)),
+    };
+    let mut doc = json!({ "version": schema::VERSION, "timestamp_ms": schema::millis(timestamp) });
+    if let (Some(doc), serde_json::Value::Object(metrics)) = (doc.as_object_mut(), metrics) {
+        doc.extend(metrics);
+    }
+    Ok(doc)
+}
+
+fn sample(sys: &System, opts: &Options) -> serde_json::Result<()> {
+    let timestamp = OffsetDateTime::now_utc();
+    let sample = collect(sys, opts.command, opts.interval.unwrap_or(Duration::from_secs(1)));
+    if opts.json {
+        println!("{}", to_json(&timestamp, &sample)?);
+    } else {
+        print(&sample);
+    }
+    Ok(())
+}
+
+fn main() {
+    let opts = match parse_args(env::args().skip(1)) {
+        Ok(opts) => opts,
+        Err(msg) if msg.is_empty() => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(msg) => {
+            eprintln!("systemstat: {}\n\n{}", msg, USAGE);
+            process::exit(2);
+        }
+    };
+    let sys = System::new();
+    let mut n = 0;
+    while opts.count.map(|count| n < count).unwrap_or(true) {
+        // The CPU load is measured over the interval, so there's no need to wait again
+        if n > 0 && opts.command != Command::Cpu && opts.command != Command::All {
+            thread::sleep(opts.interval.unwrap_or(Duration::from_secs(1)));
+        }
+        if n > 0 && !opts.json {
+            println!();
+        }
+        if let Err(err) = sample(&sys, &opts) {
+            eprintln!("systemstat: {}", err);
+            process::exit(1);
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]), Ok(Options { command: Command::All, json: false, interval: None, count: Some(1) }));
+        assert_eq!(parse(&["mem", "--json", "--interval", "0.5"]),
+                   Ok(Options { command: Command::Mem, json: true, interval: Some(Duration::from_millis(500)), count: None }));
+        assert_eq!(parse(&["--count", "3", "net"]),
+                   Ok(Options { command: Command::Net, json: false, interval: None, count: Some(3) }));
+        assert!(parse(&["--interval", "0"]).is_err());
+        assert!(parse(&["--count"]).is_err());
+        assert!(parse(&["cpu", "mem"]).is_err());
+        assert!(parse(&["swap"]).is_err());
+        assert_eq!(parse(&["--help"]), Err(String::new()));
+    }
+
+    #[test]
+    fn test_json() {
+        fn metric<T: the_serde::de::DeserializeOwned>(json: &serde_json::Value) {
+            serde_json::from_value::<schema::Metric<T>>(json.clone()).unwrap();
+        }
+
+        let sys = System::new();
+        let timestamp = OffsetDateTime::from_unix_timestamp(1700000000).unwrap();
+        let commands = [Command::Cpu, Command::Mem, Command::Disks, Command::Net, Command::Battery, Command::All];
+        for &command in &commands {
+            let json = to_json(&timestamp, &collect(&sys, command, Duration::from_millis(10))).unwrap();
+            let keys = json.as_object().unwrap().keys().map(String::as_str).collect::<Vec<_>>();
+            assert_eq!(json["version"], schema::VERSION);
+            match command {
+                Command::Cpu => {
+                    assert_eq!(keys, ["cpu_load", "timestamp_ms", "version"]);
+                    metric::<Vec<schema::CpuLoad>>(&json["cpu_load"]);
+                }
+                Command::Mem => {
+                    assert_eq!(keys, ["memory", "swap", "timestamp_ms", "version"]);
+                    metric::<schema::Memory>(&json["memory"]);
+                    metric::<schema::Memory>(&json["swap"]);
+                }
+                Command::Disks => {
+                    assert_eq!(keys, ["mounts", "timestamp_ms", "version"]);
+                    metric::<Vec<schema::Filesystem>>(&json["mounts"]);
+                }
+                Command::Net => {
+                    assert_eq!(keys, ["network_stats", "networks", "timestamp_ms", "version"]);
+                    metric::<BTreeMap<String, Vec<schema::NetworkAddrs>>>(&json["networks"]);
+                    serde_json::from_value::<BTreeMap<String, schema::Metric<schema::NetworkStats>>>(json["network_stats"].clone()).unwrap();
+                }
+                Command::Battery => {
+                    assert_eq!(keys, ["battery_life", "on_ac_power", "timestamp_ms", "version"]);
+                    metric::<schema::BatteryLife>(&json["battery_life"]);
+                    metric::<bool>(&json["on_ac_power"]);
+                }
+                Command::All => {
+                    serde_json::from_value::<schema::Snapshot>(json).unwrap();
+                    continue;
+                }
+            }
+            assert_eq!(json["timestamp_ms"], 1700000000000i64);
+        }
+    }
+}
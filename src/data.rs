@@ -3,11 +3,14 @@
 //! They're always the same across all platforms.
 
 pub use bytesize::ByteSize;
-pub use std::collections::BTreeMap;
-use std::io;
+pub use std::collections::{BTreeMap, BTreeSet};
+use std::cell::RefCell;
+use crate::error::Error;
+use std::{error, fmt, io};
 pub use std::net::{Ipv4Addr, Ipv6Addr};
 use std::ops::Sub;
 pub use std::time::Duration;
+use std::time::Instant;
 pub use time::OffsetDateTime;
 
 #[cfg(feature = "serde")]
@@ -21,19 +24,155 @@ pub fn saturating_sub_bytes(l: ByteSize, r: ByteSize) -> ByteSize {
 /// A wrapper for a measurement that takes time.
 ///
 /// Time should pass between getting the object and calling .done() on it.
+/// For measuring periodically, call .resample() instead, which also starts the next measurement
+/// from the sample it has just taken.
 pub struct DelayedMeasurement<T> {
-    res: Box<dyn Fn() -> io::Result<T> + Send>,
+    started: Instant,
+    res: Measure<T>,
+}
+
+enum Measure<T> {
+    /// Does the whole measurement
+    Once(Box<dyn Fn() -> io::Result<T> + Send>),
+    /// Takes a sample and computes the result from it, re-basing if asked to
+    Samples(Box<dyn Fn(bool) -> io::Result<T> + Send>),
 }
 
 impl<T> DelayedMeasurement<T> {
+    /// Wraps a closure that does the whole measurement.
+    ///
+    /// Measurements created this way can't be re-based, .resample() calls the same closure again.
+    /// Prefer `from_samples`.
     #[inline(always)]
     pub fn new(f: Box<dyn Fn() -> io::Result<T> + Send>) -> DelayedMeasurement<T> {
-        DelayedMeasurement { res: f }
+        DelayedMeasurement {
+            started: Instant::now(),
+            res: Measure::Once(f),
+        }
+    }
+
+    /// Takes the first sample right away, and computes the result from the sample taken
+    /// by `.done()`/`.resample()` later. `compute` gets the earlier sample, the later one,
+    /// and the time that passed between them.
+    pub fn from_samples<S, F, C>(sample: F, compute: C) -> io::Result<DelayedMeasurement<T>>
+    where
+        T: 'static,
+        S: Send + 'static,
+        F: Fn() -> io::Result<S> + Send + 'static,
+        C: Fn(&S, &S, Duration) -> T + Send + 'static,
+    {
+        let first = sample()?;
+        let started = Instant::now();
+        let prev = RefCell::new((started, first));
+        Ok(DelayedMeasurement {
+            started,
+            res: Measure::Samples(Box::new(move |rebase| {
+                let next = sample()?;
+                let now = Instant::now();
+                let mut prev = prev.borrow_mut();
+                let result = compute(&prev.1, &next, now - prev.0);
+                if rebase {
+                    *prev = (now, next);
+                }
+                Ok(result)
+            })),
+        })
+    }
+
+    /// Measures rates per second of any `Counters`.
+    pub fn rates<C, F>(sample: F) -> io::Result<DelayedMeasurement<T>>
+    where
+        T: 'static,
+        C: Counters<Rates = T> + Send + 'static,
+        F: Fn() -> io::Result<C> + Send + 'static,
+    {
+        DelayedMeasurement::from_samples(sample, C::rates)
+    }
+
+    /// Time since the measurement was started (or last re-based by .resample()).
+    #[inline(always)]
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
     }
 
     #[inline(always)]
     pub fn done(&self) -> io::Result<T> {
-        (self.res)()
+        match &self.res {
+            Measure::Once(f) => f(),
+            Measure::Samples(f) => f(false),
+        }
+    }
+
+    /// Like .done(), but the next call measures from now on rather than from the start.
+    pub fn resample(&mut self) -> io::Result<T> {
+        let result = match &self.res {
+            Measure::Once(f) => f(),
+            Measure::Samples(f) => f(true),
+        }?;
+        self.started = Instant::now();
+        Ok(result)
+    }
+
+    /// Transforms the result, keeping the ability to re-base.
+    pub fn map<U, F>(self, f: F) -> DelayedMeasurement<U>
+    where
+        T: 'static,
+        F: Fn(T) -> U + Send + 'static,
+    {
+        let res = match self.res {
+            Measure::Once(res) => Measure::Once(Box::new(move || res().map(&f))),
+            Measure::Samples(res) => Measure::Samples(Box::new(move |rebase| res(rebase).map(&f))),
+        };
+        DelayedMeasurement {
+            started: self.started,
+            res,
+        }
+    }
+}
+
+/// Monotonically increasing counters that can be turned into rates per second.
+pub trait Counters {
+    type Rates;
+
+    /// Computes the rates from counters read `elapsed` apart.
+    fn rates(prev: &Self, next: &Self, elapsed: Duration) -> Self::Rates;
+}
+
+fn per_sec(prev: u64, next: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        // A counter that went backwards (e.g. it was reset) gives a zero rate
+        next.saturating_sub(prev) as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+impl Counters for u64 {
+    type Rates = f64;
+
+    fn rates(prev: &u64, next: &u64, elapsed: Duration) -> f64 {
+        per_sec(*prev, *next, elapsed)
+    }
+}
+
+impl<T: Counters> Counters for Vec<T> {
+    type Rates = Vec<T::Rates>;
+
+    /// Pairs up the elements by position.
+    fn rates(prev: &Vec<T>, next: &Vec<T>, elapsed: Duration) -> Vec<T::Rates> {
+        prev.iter().zip(next.iter()).map(|(prev, next)| T::rates(prev, next, elapsed)).collect()
+    }
+}
+
+impl<K: Ord + Clone, T: Counters> Counters for BTreeMap<K, T> {
+    type Rates = BTreeMap<K, T::Rates>;
+
+    /// Only includes the keys present in both.
+    fn rates(prev: &BTreeMap<K, T>, next: &BTreeMap<K, T>, elapsed: Duration) -> BTreeMap<K, T::Rates> {
+        next.iter()
+            .filter_map(|(key, next)| prev.get(key).map(|prev| (key.clone(), T::rates(prev, next, elapsed))))
+            .collect()
     }
 }
 
@@ -153,6 +292,7 @@ pub struct CpuTime {
     pub other: usize,
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a> Sub<&'a CpuTime> for CpuTime {
     type Output = CpuTime;
 
@@ -408,9 +548,51 @@ pub struct Filesystem {
     derive(Serialize, Deserialize),
     serde(crate = "the_serde")
 )]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum BlockDeviceKind {
+    /// A whole physical (or virtual machine) disk, e.g. `sda` or `vda`
+    Disk,
+    /// A partition of another device, e.g. `sda1` or `nvme0n1p1`
+    Partition,
+    /// A device-mapper device (LVM, LUKS, ...), e.g. `dm-0`
+    DeviceMapper,
+    /// A software RAID device, e.g. `md0`
+    Md,
+    Loop,
+    /// A RAM disk, e.g. `ram0`
+    Ram,
+    /// A compressed RAM disk, usually used for swap
+    Zram,
+    /// An NVMe namespace, e.g. `nvme0n1`
+    NvmeNamespace,
+    /// An optical (CD/DVD) drive, e.g. `sr0`
+    Optical,
+}
+
+impl BlockDeviceKind {
+    /// Whether the device is a physical disk, as opposed to a part of one or a device
+    /// built on top of others, whose I/O is counted again on the underlying disks
+    pub fn is_physical_disk(self) -> bool {
+        matches!(self, BlockDeviceKind::Disk | BlockDeviceKind::NvmeNamespace)
+    }
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+/// Counters as found in `/proc/diskstats`.
+///
+/// More fields may be added, so outside of this crate it can only be created with `sum`.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct BlockDeviceStats {
     pub name: String,
+    pub kind: BlockDeviceKind,
+    /// For partitions, the name of the device they're on
+    pub parent: Option<String>,
     pub read_ios: usize,
     pub read_merges: usize,
     pub read_sectors: usize,
@@ -424,6 +606,104 @@ pub struct BlockDeviceStats {
     pub time_in_queue: usize,
 }
 
+impl BlockDeviceStats {
+    /// Adds up the counters of several devices, e.g. all the physical disks.
+    /// Note that `io_ticks` is then the sum of the busy times, which can exceed the elapsed time.
+    pub fn sum<'a>(name: &str, stats: impl IntoIterator<Item = &'a BlockDeviceStats>) -> BlockDeviceStats {
+        let mut total = BlockDeviceStats {
+            name: name.to_string(),
+            kind: BlockDeviceKind::Disk,
+            parent: None,
+            read_ios: 0,
+            read_merges: 0,
+            read_sectors: 0,
+            read_ticks: 0,
+            write_ios: 0,
+            write_merges: 0,
+            write_sectors: 0,
+            write_ticks: 0,
+            in_flight: 0,
+            io_ticks: 0,
+            time_in_queue: 0,
+        };
+        for stats in stats {
+            total.read_ios += stats.read_ios;
+            total.read_merges += stats.read_merges;
+            total.read_sectors += stats.read_sectors;
+            total.read_ticks += stats.read_ticks;
+            total.write_ios += stats.write_ios;
+            total.write_merges += stats.write_merges;
+            total.write_sectors += stats.write_sectors;
+            total.write_ticks += stats.write_ticks;
+            total.in_flight += stats.in_flight;
+            total.io_ticks += stats.io_ticks;
+            total.time_in_queue += stats.time_in_queue;
+        }
+        total
+    }
+}
+
+/// iostat-style disk metrics, computed from two `BlockDeviceStats`.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskIoRates {
+    /// Percentage of the time the device was busy. Devices that serve requests in parallel
+    /// (SSDs, RAID) can be at 100% without being saturated.
+    pub utilization: f64,
+    /// Read requests completed per second
+    pub reads: f64,
+    /// Write requests completed per second
+    pub writes: f64,
+    /// Kibibytes read per second
+    pub read_kib: f64,
+    /// Kibibytes written per second
+    pub write_kib: f64,
+    /// Average time to serve a request, including the time in the queue, in milliseconds
+    pub await_ms: f64,
+    /// Average number of requests in the queue or being served
+    pub queue_size: f64,
+    /// Average size of a request, in kibibytes
+    pub request_kib: f64,
+}
+
+impl DiskIoRates {
+    /// Computes the rates from statistics taken `elapsed` apart.
+    /// A counter that went backwards (e.g. the device was removed and added again) counts as zero.
+    pub fn between(prev: &BlockDeviceStats, next: &BlockDeviceStats, elapsed: Duration) -> DiskIoRates {
+        BlockDeviceStats::rates(prev, next, elapsed)
+    }
+}
+
+impl Counters for BlockDeviceStats {
+    type Rates = DiskIoRates;
+
+    fn rates(prev: &BlockDeviceStats, next: &BlockDeviceStats, elapsed: Duration) -> DiskIoRates {
+        let delta = |prev: usize, next: usize| next.saturating_sub(prev) as u64;
+        let rate = |prev: usize, next: usize| per_sec(0, delta(prev, next), elapsed);
+        let ratio = |num: u64, den: u64| if den > 0 { num as f64 / den as f64 } else { 0.0 };
+        // Sectors are always 512 bytes here, whatever the device's sector size
+        let sectors_kib = |sectors: f64| sectors / 2.0;
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        let ios = delta(prev.read_ios, next.read_ios) + delta(prev.write_ios, next.write_ios);
+        let sectors = delta(prev.read_sectors, next.read_sectors) + delta(prev.write_sectors, next.write_sectors);
+        let ticks = delta(prev.read_ticks, next.read_ticks) + delta(prev.write_ticks, next.write_ticks);
+        DiskIoRates {
+            utilization: if ms > 0.0 { (delta(prev.io_ticks, next.io_ticks) as f64 / ms * 100.0).min(100.0) } else { 0.0 },
+            reads: rate(prev.read_ios, next.read_ios),
+            writes: rate(prev.write_ios, next.write_ios),
+            read_kib: sectors_kib(rate(prev.read_sectors, next.read_sectors)),
+            write_kib: sectors_kib(rate(prev.write_sectors, next.write_sectors)),
+            await_ms: ratio(ticks, ios),
+            queue_size: if ms > 0.0 { delta(prev.time_in_queue, next.time_in_queue) as f64 / ms } else { 0.0 },
+            request_kib: sectors_kib(ratio(sectors, ios)),
+        }
+    }
+}
+
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -474,6 +754,73 @@ pub struct NetworkStats {
     pub tx_errors: u64,
 }
 
+/// Network interface statistics per second, computed from two `NetworkStats`.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkRates {
+    pub rx_bytes: f64,
+    pub tx_bytes: f64,
+    pub rx_packets: f64,
+    pub tx_packets: f64,
+    pub rx_errors: f64,
+    pub tx_errors: f64,
+}
+
+impl NetworkRates {
+    /// Computes the rates from statistics taken `elapsed` apart.
+    /// A counter that went backwards (e.g. the interface was reset) gives a zero rate.
+    pub fn between(prev: &NetworkStats, next: &NetworkStats, elapsed: Duration) -> NetworkRates {
+        NetworkStats::rates(prev, next, elapsed)
+    }
+}
+
+impl Counters for NetworkStats {
+    type Rates = NetworkRates;
+
+    fn rates(prev: &NetworkStats, next: &NetworkStats, elapsed: Duration) -> NetworkRates {
+        NetworkRates {
+            rx_bytes: per_sec(prev.rx_bytes.as_u64(), next.rx_bytes.as_u64(), elapsed),
+            tx_bytes: per_sec(prev.tx_bytes.as_u64(), next.tx_bytes.as_u64(), elapsed),
+            rx_packets: per_sec(prev.rx_packets, next.rx_packets, elapsed),
+            tx_packets: per_sec(prev.tx_packets, next.tx_packets, elapsed),
+            rx_errors: per_sec(prev.rx_errors, next.rx_errors, elapsed),
+            tx_errors: per_sec(prev.tx_errors, next.tx_errors, elapsed),
+        }
+    }
+}
+
+/// Link quality and error counters of a wireless interface.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WirelessStats {
+    /// Link quality, on a scale that depends on the driver (often 0 to 70)
+    pub link_quality: f32,
+    /// Signal level in dBm, if the driver reports it
+    pub signal_dbm: Option<f32>,
+    /// Noise level in dBm, if the driver reports it
+    pub noise_dbm: Option<f32>,
+    /// Packets discarded because of a different network ID
+    pub discarded_nwid: u64,
+    /// Packets discarded because they couldn't be decrypted
+    pub discarded_crypt: u64,
+    /// Packets discarded because they couldn't be reassembled
+    pub discarded_frag: u64,
+    /// Packets that couldn't be delivered after retrying
+    pub discarded_retry: u64,
+    /// Packets discarded for other reasons
+    pub discarded_misc: u64,
+    /// Beacons missed from the access point
+    pub missed_beacons: u64,
+}
+
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -487,3 +834,831 @@ pub struct SocketStats {
     pub tcp6_sockets_in_use: usize,
     pub udp6_sockets_in_use: usize,
 }
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Virtualization {
+    Docker,
+    Podman,
+    Lxc,
+    Kubernetes,
+    Kvm,
+    Qemu,
+    VMware,
+    VirtualBox,
+    Xen,
+    HyperV,
+    Other(String),
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    /// Lower-case identifier, e.g. `debian` (`ID` in os-release)
+    pub id: String,
+    /// Human-readable name, e.g. `Debian GNU/Linux` (`NAME` in os-release)
+    pub name: String,
+    /// Human-readable version, e.g. `12 (bookworm)` (`VERSION` in os-release)
+    pub version: Option<String>,
+    /// Machine-readable version, e.g. `12` (`VERSION_ID` in os-release)
+    pub version_id: Option<String>,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct OsInfo {
+    pub hostname: String,
+    /// Kernel name, e.g. `Linux`, `FreeBSD` or `Windows`
+    pub kernel_name: String,
+    /// Kernel release, e.g. `6.1.0-18-amd64`, or the `major.minor.build` version on Windows
+    pub kernel_release: String,
+    /// Kernel version, or the service pack on Windows (usually empty)
+    pub kernel_version: String,
+    /// Machine hardware name, e.g. `x86_64`
+    pub machine: String,
+    /// Only available on Linux
+    pub distribution: Option<Distribution>,
+    /// Only detected on Linux, `None` if running on bare metal (or if detection failed)
+    pub virtualization: Option<Virtualization>,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct UserSession {
+    pub username: String,
+    /// Terminal line, e.g. `pts/0` or `tty1`
+    pub tty: String,
+    /// Remote host the user logged in from, empty for local sessions
+    pub host: String,
+    pub login_time: OffsetDateTime,
+    /// Process ID of the login process (usually the shell or sshd)
+    pub pid: u32,
+}
+
+/// The chassis type, as defined in the SMBIOS specification.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChassisType {
+    Unknown,
+    Desktop,
+    LowProfileDesktop,
+    PizzaBox,
+    MiniTower,
+    Tower,
+    Portable,
+    Laptop,
+    Notebook,
+    HandHeld,
+    DockingStation,
+    AllInOne,
+    SubNotebook,
+    MainServerChassis,
+    RackMountChassis,
+    Blade,
+    Tablet,
+    Convertible,
+    Detachable,
+    MiniPc,
+    StickPc,
+    /// Any other type code, see the SMBIOS specification for the meaning
+    Other(u8),
+}
+
+impl ChassisType {
+    pub fn from_smbios(code: u8) -> ChassisType {
+        match code {
+            2 => ChassisType::Unknown,
+            3 => ChassisType::Desktop,
+            4 => ChassisType::LowProfileDesktop,
+            5 => ChassisType::PizzaBox,
+            6 => ChassisType::MiniTower,
+            7 => ChassisType::Tower,
+            8 => ChassisType::Portable,
+            9 => ChassisType::Laptop,
+            10 => ChassisType::Notebook,
+            11 => ChassisType::HandHeld,
+            12 => ChassisType::DockingStation,
+            13 => ChassisType::AllInOne,
+            14 => ChassisType::SubNotebook,
+            17 => ChassisType::MainServerChassis,
+            23 => ChassisType::RackMountChassis,
+            28 => ChassisType::Blade,
+            30 => ChassisType::Tablet,
+            31 => ChassisType::Convertible,
+            32 => ChassisType::Detachable,
+            35 => ChassisType::MiniPc,
+            36 => ChassisType::StickPc,
+            _ => ChassisType::Other(code),
+        }
+    }
+
+    pub fn to_smbios(self) -> u8 {
+        match self {
+            ChassisType::Unknown => 2,
+            ChassisType::Desktop => 3,
+            ChassisType::LowProfileDesktop => 4,
+            ChassisType::PizzaBox => 5,
+            ChassisType::MiniTower => 6,
+            ChassisType::Tower => 7,
+            ChassisType::Portable => 8,
+            ChassisType::Laptop => 9,
+            ChassisType::Notebook => 10,
+            ChassisType::HandHeld => 11,
+            ChassisType::DockingStation => 12,
+            ChassisType::AllInOne => 13,
+            ChassisType::SubNotebook => 14,
+            ChassisType::MainServerChassis => 17,
+            ChassisType::RackMountChassis => 23,
+            ChassisType::Blade => 28,
+            ChassisType::Tablet => 30,
+            ChassisType::Convertible => 31,
+            ChassisType::Detachable => 32,
+            ChassisType::MiniPc => 35,
+            ChassisType::StickPc => 36,
+            ChassisType::Other(code) => code,
+        }
+    }
+}
+
+/// Hardware identification from DMI/SMBIOS.
+///
+/// Every field is optional: some are not filled in by the firmware,
+/// and some (like `serial` and `uuid`) are only readable by root.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct HardwareInfo {
+    pub system_vendor: Option<String>,
+    pub product_name: Option<String>,
+    pub serial: Option<String>,
+    pub uuid: Option<String>,
+    pub board_vendor: Option<String>,
+    pub board_name: Option<String>,
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    pub bios_date: Option<String>,
+    pub chassis_type: Option<ChassisType>,
+}
+
+/// Usage of kernel tables that can run out, and their limits.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct KernelLimits {
+    /// Allocated file handles
+    pub file_handles: u64,
+    /// Maximum number of file handles (often `i64::MAX`, i.e. unlimited)
+    pub file_handles_max: u64,
+    /// Allocated inodes
+    pub inodes: u64,
+    /// Allocated but unused inodes
+    pub inodes_free: u64,
+    /// Scheduling entities (processes and threads) that currently exist, i.e. PIDs in use
+    pub pids: u64,
+    /// Largest PID the kernel will allocate
+    pub pid_max: u64,
+    /// Maximum number of threads
+    pub threads_max: u64,
+    /// Available entropy in the kernel's random pool, in bits
+    pub entropy_avail: u64,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InterruptKind {
+    Hardware,
+    Soft,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct Interrupt {
+    pub kind: InterruptKind,
+    /// IRQ number or name, e.g. `25` or `LOC` for hardware interrupts, `NET_RX` for softirqs
+    pub irq: String,
+    /// Interrupt counts, one per CPU (core), in the same order as `cpu_load()`
+    pub per_cpu: Vec<u64>,
+    /// Interrupt controller, trigger type and device names (empty for softirqs)
+    pub description: String,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct InterruptRate {
+    pub kind: InterruptKind,
+    pub irq: String,
+    /// Interrupts per second, one per CPU (core), in the same order as `cpu_load()`
+    pub per_cpu: Vec<f64>,
+    pub description: String,
+}
+
+impl Counters for Vec<Interrupt> {
+    type Rates = Vec<InterruptRate>;
+
+    /// Pairs up the interrupts by kind and IRQ.
+    fn rates(prev: &Vec<Interrupt>, next: &Vec<Interrupt>, elapsed: Duration) -> Vec<InterruptRate> {
+        next.iter()
+            .filter_map(|next| {
+                let prev = prev.iter().find(|p| p.kind == next.kind && p.irq == next.irq)?;
+                Some(InterruptRate {
+                    kind: next.kind,
+                    irq: next.irq.clone(),
+                    per_cpu: Counters::rates(&prev.per_cpu, &next.per_cpu, elapsed),
+                    description: next.description.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The CPUs that the current process can use.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuAvailability {
+    /// IDs of the CPUs (cores) that are online
+    pub online: Vec<usize>,
+    /// IDs of the CPUs the current process is allowed to run on (its affinity mask)
+    pub affinity: Vec<usize>,
+    /// The CPU time the process's cgroup is allowed to use, in CPUs,
+    /// e.g. 1.5 for a quota of 150ms every 100ms. `None` if it's not limited.
+    pub quota: Option<f64>,
+}
+
+impl CpuAvailability {
+    /// The number of threads that can usefully run in parallel, like
+    /// `std::thread::available_parallelism`: the number of CPUs in the affinity mask,
+    /// limited by the quota (rounded up), and at least 1.
+    pub fn parallelism(&self) -> usize {
+        let cpus = self.affinity.len();
+        match self.quota {
+            Some(quota) => cpus.min(quota.ceil() as usize),
+            None => cpus,
+        }
+        .max(1)
+    }
+}
+
+/// Open file descriptors of a process, counted by type.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessFds {
+    /// Files, directories and devices
+    pub files: usize,
+    pub sockets: usize,
+    pub pipes: usize,
+    /// Anonymous inodes, e.g. eventfd, epoll, inotify, timerfd
+    pub anon_inodes: usize,
+    /// Anything else, e.g. namespaces
+    pub other: usize,
+}
+
+impl ProcessFds {
+    pub fn total(&self) -> usize {
+        self.files + self.sockets + self.pipes + self.anon_inodes + self.other
+    }
+}
+
+/// I/O done by a process since it started.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessIo {
+    /// Read by system calls, including from the page cache, pipes, sockets, etc.
+    pub read_chars: ByteSize,
+    /// Written by system calls
+    pub written_chars: ByteSize,
+    /// Number of read system calls
+    pub read_syscalls: u64,
+    /// Number of write system calls
+    pub write_syscalls: u64,
+    /// Actually fetched from storage
+    pub read_bytes: ByteSize,
+    /// Caused to be sent to storage
+    pub write_bytes: ByteSize,
+    /// Written to the page cache but truncated before reaching storage
+    pub cancelled_write_bytes: ByteSize,
+}
+
+/// Memory used by a process, accounting for pages shared with other processes.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessMemory {
+    /// Resident set size: all the memory mapped into RAM, including shared pages
+    pub rss: ByteSize,
+    /// Proportional set size: private memory plus a share of each shared page,
+    /// divided by the number of processes sharing it
+    pub pss: ByteSize,
+    /// Unique set size: private memory, which would be freed if the process exited
+    pub uss: ByteSize,
+    /// Memory shared with other processes
+    pub shared: ByteSize,
+    /// Swapped out memory
+    pub swap: ByteSize,
+    /// Proportional share of swapped out memory
+    pub swap_pss: ByteSize,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProcessState {
+    Running,
+    /// Waiting for an event
+    Sleeping,
+    /// Waiting for I/O, uninterruptibly
+    DiskSleep,
+    Stopped,
+    /// Stopped by a debugger
+    TracingStop,
+    /// Exited, but not yet waited for by the parent
+    Zombie,
+    Dead,
+    /// An idle kernel thread
+    Idle,
+    Unknown,
+}
+
+/// A running process.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub pid: u32,
+    /// Parent process ID, 0 if there's none
+    pub ppid: u32,
+    /// Executable name (on Linux, truncated to 15 bytes)
+    pub name: String,
+    pub state: ProcessState,
+    /// Real user ID
+    pub uid: u32,
+    /// Name of the user, if it's in the user database
+    pub username: Option<String>,
+    /// Control group path, e.g. `/system.slice/nginx.service`
+    pub cgroup: Option<String>,
+    /// CPU time spent in user mode since the process started
+    pub user_time: Duration,
+    /// CPU time spent in kernel mode since the process started
+    pub system_time: Duration,
+    /// Resident set size
+    pub rss: ByteSize,
+}
+
+impl Process {
+    pub fn cpu_time(&self) -> Duration {
+        self.user_time + self.system_time
+    }
+}
+
+/// A thread of a process.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct Thread {
+    /// Thread ID (the main thread's is the process ID)
+    pub tid: u32,
+    /// Thread name (on Linux, truncated to 15 bytes)
+    pub name: String,
+    pub state: ProcessState,
+    /// The CPU (core) the thread last ran on
+    pub last_cpu: Option<usize>,
+    /// CPU time spent in user mode since the thread started
+    pub user_time: Duration,
+    /// CPU time spent in kernel mode since the thread started
+    pub system_time: Duration,
+}
+
+impl Thread {
+    pub fn cpu_time(&self) -> Duration {
+        self.user_time + self.system_time
+    }
+}
+
+/// The CPU used by a thread over a measurement.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct ThreadLoad {
+    pub tid: u32,
+    pub name: String,
+    pub state: ProcessState,
+    pub last_cpu: Option<usize>,
+    /// Fraction of one CPU spent in user mode
+    pub user: f32,
+    /// Fraction of one CPU spent in kernel mode
+    pub system: f32,
+}
+
+impl ThreadLoad {
+    /// Fraction of one CPU used in total
+    pub fn total(&self) -> f32 {
+        self.user + self.system
+    }
+}
+
+impl Counters for Vec<Thread> {
+    type Rates = Vec<ThreadLoad>;
+
+    /// Pairs up the threads by ID. Threads that only appear in `next` started in the meantime,
+    /// so all their CPU time counts.
+    fn rates(prev: &Vec<Thread>, next: &Vec<Thread>, elapsed: Duration) -> Vec<ThreadLoad> {
+        next.iter()
+            .map(|next| {
+                let (user, system) = prev
+                    .iter()
+                    .find(|p| p.tid == next.tid)
+                    .map_or((Duration::ZERO, Duration::ZERO), |prev| (prev.user_time, prev.system_time));
+                let secs = elapsed.as_secs_f64();
+                let fraction = |prev: Duration, next: Duration| {
+                    if secs > 0.0 {
+                        (next.saturating_sub(prev).as_secs_f64() / secs) as f32
+                    } else {
+                        0.0
+                    }
+                };
+                ThreadLoad {
+                    tid: next.tid,
+                    name: next.name.clone(),
+                    state: next.state,
+                    last_cpu: next.last_cpu,
+                    user: fraction(user, next.user_time),
+                    system: fraction(system, next.system_time),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A kind of information that can be obtained through `Platform`.
+///
+/// Named after the corresponding `Platform` functions.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    CpuLoad,
+    LoadAverage,
+    Memory,
+    Swap,
+    Uptime,
+    BootTime,
+    BatteryLife,
+    OnAcPower,
+    Mounts,
+    BlockDeviceStatistics,
+    DiskIoRates,
+    Networks,
+    NetworkStats,
+    CpuTemp,
+    SocketStats,
+    OsInfo,
+    Users,
+    HardwareInfo,
+    KernelLimits,
+    Interrupts,
+    CpuAvailability,
+    ProcessFds,
+    ProcessIo,
+    ProcessMemory,
+    Processes,
+    Threads,
+    Wireless,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CgroupVersion {
+    /// Legacy hierarchy only
+    V1,
+    /// Legacy hierarchy with the unified one mounted at `/sys/fs/cgroup/unified`
+    Hybrid,
+    /// Unified hierarchy only
+    V2,
+}
+
+/// The kinds of information supported on the current platform and in the current environment.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    pub supported: BTreeSet<Capability>,
+    /// Whether procfs is mounted at `/proc` (Linux only)
+    pub procfs: bool,
+    /// Whether pressure stall information is available in `/proc/pressure` (Linux only)
+    pub pressure: bool,
+    /// The cgroup hierarchy mounted at `/sys/fs/cgroup`, if any (Linux only)
+    pub cgroups: Option<CgroupVersion>,
+}
+
+impl Capabilities {
+    pub fn new(supported: &[Capability]) -> Capabilities {
+        Capabilities {
+            supported: supported.iter().copied().collect(),
+            procfs: false,
+            pressure: false,
+            cgroups: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.supported.contains(&capability)
+    }
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotErrorKind {
+    Unsupported,
+    NotPresent,
+    Parse,
+    Io,
+}
+
+/// A serializable description of why a metric is missing from a `Snapshot`.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotError {
+    pub kind: SnapshotErrorKind,
+    pub message: String,
+    /// For `NotPresent`, the description of what's missing from `Error::NotPresent`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub detail: Option<String>,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+impl error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> SnapshotError {
+        if let Some(err) = err.get_ref().and_then(|inner| inner.downcast_ref::<SnapshotError>()) {
+            return err.clone();
+        }
+        let err = Error::from(err);
+        SnapshotError {
+            kind: match err {
+                Error::Unsupported => SnapshotErrorKind::Unsupported,
+                Error::NotPresent(_) => SnapshotErrorKind::NotPresent,
+                Error::Parse { .. } => SnapshotErrorKind::Parse,
+                Error::Io(_) => SnapshotErrorKind::Io,
+            },
+            message: err.to_string(),
+            detail: match &err {
+                Error::NotPresent(what) => Some(what.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl From<SnapshotError> for io::Error {
+    /// Turns the error back into an `io::Error`, e.g. for replaying a snapshot.
+    /// `Error::from` on the result gives `Unsupported`/`NotPresent` for these kinds,
+    /// and `Io` with the original message for the others.
+    fn from(err: SnapshotError) -> io::Error {
+        match err.kind {
+            SnapshotErrorKind::Unsupported => Error::Unsupported.into(),
+            SnapshotErrorKind::NotPresent => match err.detail {
+                Some(what) => Error::NotPresent(what).into(),
+                None => io::Error::new(io::ErrorKind::NotFound, err),
+            },
+            SnapshotErrorKind::Parse => io::Error::new(io::ErrorKind::InvalidData, err),
+            SnapshotErrorKind::Io => io::Error::other(err),
+        }
+    }
+}
+
+pub type SnapshotResult<T> = Result<T, SnapshotError>;
+
+/// Options for `Platform::snapshot`.
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    /// How long to measure the CPU load for, `None` to skip it.
+    ///
+    /// The other metrics are collected while waiting.
+    pub cpu_load_duration: Option<Duration>,
+    /// Whether to collect network statistics for every interface
+    pub network_stats: bool,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> SnapshotOptions {
+        SnapshotOptions {
+            cpu_load_duration: Some(Duration::from_secs(1)),
+            network_stats: true,
+        }
+    }
+}
+
+/// Most of the information available through `Platform`, collected at once.
+///
+/// Every field is a separate `Result`, so that e.g. the lack of a battery doesn't lose the rest.
+/// Delayed measurements other than `cpu_load` (e.g. `disk_io_rates`), `wireless`,
+/// `cpu_availability` and the per-process methods (e.g. `processes`) aren't included.
+/// More fields may be added, which is why the struct is `non_exhaustive`.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Snapshot {
+    /// When the collection started
+    pub timestamp: OffsetDateTime,
+    /// `None` if skipped in the options
+    pub cpu_load: Option<SnapshotResult<Vec<CPULoad>>>,
+    pub load_average: SnapshotResult<LoadAverage>,
+    pub memory: SnapshotResult<Memory>,
+    pub swap: SnapshotResult<Swap>,
+    pub uptime: SnapshotResult<Duration>,
+    pub boot_time: SnapshotResult<OffsetDateTime>,
+    pub battery_life: SnapshotResult<BatteryLife>,
+    pub on_ac_power: SnapshotResult<bool>,
+    pub mounts: SnapshotResult<Vec<Filesystem>>,
+    pub block_device_statistics: SnapshotResult<BTreeMap<String, BlockDeviceStats>>,
+    pub networks: SnapshotResult<BTreeMap<String, Network>>,
+    /// Statistics for every interface in `networks`, empty if skipped in the options
+    pub network_stats: BTreeMap<String, SnapshotResult<NetworkStats>>,
+    pub cpu_temp: SnapshotResult<f32>,
+    pub socket_stats: SnapshotResult<SocketStats>,
+    pub os_info: SnapshotResult<OsInfo>,
+    pub users: SnapshotResult<Vec<UserSession>>,
+    pub hardware_info: SnapshotResult<HardwareInfo>,
+    pub kernel_limits: SnapshotResult<KernelLimits>,
+    pub interrupts: SnapshotResult<Vec<Interrupt>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_error_round_trip() {
+        let err = SnapshotError::from(io::Error::from(Error::NotPresent("battery".to_string())));
+        assert_eq!((err.kind, err.message.as_str(), err.detail.as_deref()), (SnapshotErrorKind::NotPresent, "Missing battery", Some("battery")));
+        assert!(matches!(Error::from(io::Error::from(err)), Error::NotPresent(what) if what == "battery"));
+
+        // Only `detail` matters, not the wording of the message
+        let err = SnapshotError {
+            kind: SnapshotErrorKind::NotPresent,
+            message: "No battery".to_string(),
+            detail: Some("battery".to_string()),
+        };
+        assert!(matches!(Error::from(io::Error::from(err)), Error::NotPresent(what) if what == "battery"));
+    }
+
+    #[test]
+    fn test_thread_rates() {
+        let thread = |tid, user_ms, system_ms| Thread {
+            tid,
+            name: format!("worker-{}", tid),
+            state: ProcessState::Running,
+            last_cpu: Some(0),
+            user_time: Duration::from_millis(user_ms),
+            system_time: Duration::from_millis(system_ms),
+        };
+        let prev = vec![thread(1, 1000, 100), thread(2, 500, 0)];
+        let next = vec![thread(1, 2500, 600), thread(3, 200, 0)];
+        let load = Counters::rates(&prev, &next, Duration::from_secs(2));
+        assert_eq!(load.len(), 2);
+        assert_eq!((load[0].user, load[0].system, load[0].total()), (0.75, 0.25, 1.0));
+        // Started in the meantime
+        assert_eq!(load[1].tid, 3);
+        assert_eq!(load[1].user, 0.1);
+        assert_eq!(next[0].cpu_time(), Duration::from_millis(3100));
+    }
+
+    #[test]
+    fn test_disk_io_rates() {
+        let stats = |ios: usize, sectors: usize, ticks: usize, io_ticks: usize| BlockDeviceStats {
+            name: "sda".to_string(),
+            kind: BlockDeviceKind::Disk,
+            parent: None,
+            read_ios: ios,
+            read_merges: 0,
+            read_sectors: sectors,
+            read_ticks: ticks,
+            write_ios: ios / 2,
+            write_merges: 0,
+            write_sectors: sectors / 2,
+            write_ticks: ticks,
+            in_flight: 0,
+            io_ticks,
+            time_in_queue: 2 * ticks,
+        };
+        let rates = DiskIoRates::between(&stats(100, 800, 50, 100), &stats(300, 2400, 250, 600), Duration::from_secs(2));
+        assert_eq!((rates.reads, rates.writes), (100.0, 50.0));
+        assert_eq!((rates.read_kib, rates.write_kib), (400.0, 200.0));
+        assert_eq!(rates.utilization, 25.0);
+        // 400 ms over 300 requests, in the queue for 400 ms out of 2000
+        assert_eq!(rates.await_ms, 400.0 / 300.0);
+        assert_eq!(rates.queue_size, 0.2);
+        assert_eq!(rates.request_kib, 4.0);
+        let idle = DiskIoRates::between(&stats(100, 800, 50, 100), &stats(100, 800, 50, 100), Duration::from_secs(1));
+        assert_eq!((idle.await_ms, idle.request_kib, idle.utilization), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_network_rates() {
+        let stats = |rx: u64, packets: u64| NetworkStats {
+            rx_bytes: ByteSize::b(rx),
+            tx_bytes: ByteSize::b(0),
+            rx_packets: packets,
+            tx_packets: 0,
+            rx_errors: 0,
+            tx_errors: 0,
+        };
+        let rates = NetworkRates::between(&stats(1000, 10), &stats(3000, 5), Duration::from_millis(500));
+        assert_eq!(rates.rx_bytes, 4000.0);
+        assert_eq!(rates.rx_packets, 0.0);
+        assert_eq!(rates.tx_bytes, 0.0);
+    }
+}
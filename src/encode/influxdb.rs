@@ -0,0 +1,109 @@
+//! Encoder for the InfluxDB line protocol.
+
+use super::points::*;
+use crate::data::OffsetDateTime;
+use std::fmt::Write;
+
+fn escape_measurement(value: &str) -> String {
+    value.replace(',', "\\,").replace(' ', "\\ ")
+}
+
+fn escape_tag(value: &str) -> String {
+    value.replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+/// Encodes a value as InfluxDB line protocol, one line per point.
+///
+/// `tags` are added to every point (e.g. `[("host", "example.com")]`), and `timestamp`
+/// is written in nanoseconds. Tags are sorted by key and tags with empty values are skipped,
+/// as recommended by InfluxDB. Fields that aren't finite (NaN or infinite) can't be represented
+/// and are skipped too, and integers above `i64::MAX` are clamped.
+pub fn encode<T: ToPoints + ?Sized>(value: &T, tags: &[(&str, &str)], timestamp: OffsetDateTime) -> String {
+    let timestamp = timestamp.unix_timestamp_nanos();
+    let mut out = String::new();
+    for point in value.to_points() {
+        let fields = point
+            .fields
+            .iter()
+            .filter(|(_, value)| !matches!(value, Value::Float(v) if !v.is_finite()))
+            .collect::<Vec<_>>();
+        if fields.is_empty() {
+            continue;
+        }
+        let mut all_tags = point
+            .tags
+            .iter()
+            .map(|(k, v)| (*k, v.as_str()))
+            .chain(tags.iter().copied())
+            .filter(|(_, v)| !v.is_empty())
+            .collect::<Vec<_>>();
+        all_tags.sort_by_key(|(k, _)| *k);
+        out.push_str(&escape_measurement(point.measurement));
+        for (key, value) in all_tags {
+            let _ = write!(out, ",{}={}", escape_tag(key), escape_tag(value));
+        }
+        for (i, (key, value)) in fields.into_iter().enumerate() {
+            out.push(if i == 0 { ' ' } else { ',' });
+            let _ = match value {
+                Value::Float(v) => write!(out, "{}={}", escape_tag(key), v),
+                // Integer fields are signed 64-bit
+                Value::Integer(v) | Value::Counter(v) => write!(out, "{}={}i", escape_tag(key), (*v).min(i64::MAX as u64)),
+                Value::Boolean(v) => write!(out, "{}={}", escape_tag(key), v),
+            };
+        }
+        let _ = writeln!(out, " {}", timestamp);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::*;
+
+    #[test]
+    fn test_encode() {
+        let snapshot = crate::encode::test_snapshot();
+        let out = encode(&snapshot, &[("host", "web 1"), ("dc", "")], snapshot.timestamp);
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "cpu,cpu=0,host=web\\ 1 user=0.25,nice=0,system=0.25,interrupt=0,idle=0.5,other=0 1700000000000000000");
+        assert!(lines.contains(&"system,host=web\\ 1 load1=0.5,load5=0.25,load15=0.125 1700000000000000000"));
+        assert!(lines.contains(&"system,host=web\\ 1 uptime=3600i,n_users=0i 1700000000000000000"));
+        assert!(lines.contains(&"power,host=web\\ 1 on_ac=true 1700000000000000000"));
+        assert!(lines.contains(&"net,host=web\\ 1,interface=eth0 bytes_recv=1000i,bytes_sent=2000i,packets_recv=10i,packets_sent=20i,err_in=1i,err_out=0i 1700000000000000000"));
+        assert!(lines.iter().any(|l| l.starts_with("disk,device=/dev/sda1,fstype=ext4,host=web\\ 1,path=/ total=1048576i,")));
+        assert!(!out.contains("mem"));
+    }
+
+    #[test]
+    fn test_encode_data_type() {
+        let load = LoadAverage { one: 1.5, five: 1.0, fifteen: 0.5 };
+        let out = encode(&load, &[], OffsetDateTime::from_unix_timestamp(1).unwrap());
+        assert_eq!(out, "system load1=1.5,load5=1,load15=0.5 1000000000\n");
+    }
+
+    #[test]
+    fn test_encode_non_finite() {
+        let load = LoadAverage { one: f32::NAN, five: 1.0, fifteen: f32::INFINITY };
+        let out = encode(&load, &[], OffsetDateTime::from_unix_timestamp(1).unwrap());
+        assert_eq!(out, "system load5=1 1000000000\n");
+        let load = LoadAverage { one: f32::NAN, five: f32::NAN, fifteen: f32::NAN };
+        assert_eq!(encode(&load, &[], OffsetDateTime::from_unix_timestamp(1).unwrap()), "");
+    }
+
+    #[test]
+    fn test_encode_clamped() {
+        let limits = KernelLimits {
+            file_handles: 1024,
+            file_handles_max: u64::MAX,
+            inodes: 0,
+            inodes_free: 0,
+            pids: 0,
+            pid_max: 0,
+            threads_max: 0,
+            entropy_avail: 0,
+        };
+        let out = encode(&limits, &[], OffsetDateTime::from_unix_timestamp(1).unwrap());
+        assert!(out.contains("file_handles_max=9223372036854775807i"), "{}", out);
+    }
+}
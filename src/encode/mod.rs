@@ -0,0 +1,109 @@
+//! This module provides encoders that turn system information into the text formats
+//! of monitoring systems.
+//!
+//! Each encoder is enabled by the Cargo feature of the same name. The line-based encoders
+//! work on anything that implements `ToPoints`, i.e. the data types and the whole `Snapshot`
+//! (`ToPoints` is only available when one of them is enabled).
+
+#[cfg(any(feature = "influxdb", feature = "statsd"))]
+mod points;
+#[cfg(any(feature = "influxdb", feature = "statsd"))]
+pub use self::points::*;
+
+#[cfg(feature = "influxdb")]
+pub mod influxdb;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+#[cfg(feature = "statsd")]
+pub mod statsd;
+
+/// A snapshot with fixed values for testing the encoders and the stable schema.
+#[cfg(all(test, any(feature = "influxdb", feature = "prometheus", feature = "serde", feature = "statsd")))]
+pub(crate) fn test_snapshot() -> crate::data::Snapshot {
+    use crate::data::*;
+
+    let unsupported = || SnapshotError {
+        kind: SnapshotErrorKind::Unsupported,
+        message: "Not supported".to_string(),
+        detail: None,
+    };
+    let mut networks = BTreeMap::new();
+    networks.insert("eth0".to_string(), Network { name: "eth0".to_string(), addrs: Vec::new() });
+    let mut network_stats = BTreeMap::new();
+    network_stats.insert("eth0".to_string(), Ok(NetworkStats {
+        rx_bytes: ByteSize::b(1000),
+        tx_bytes: ByteSize::b(2000),
+        rx_packets: 10,
+        tx_packets: 20,
+        rx_errors: 1,
+        tx_errors: 0,
+    }));
+    let mut block_devices = BTreeMap::new();
+    block_devices.insert("sda".to_string(), BlockDeviceStats {
+        name: "sda".to_string(),
+        kind: BlockDeviceKind::Disk,
+        parent: None,
+        read_ios: 100,
+        read_merges: 5,
+        read_sectors: 800,
+        read_ticks: 1500,
+        write_ios: 50,
+        write_merges: 2,
+        write_sectors: 400,
+        write_ticks: 700,
+        in_flight: 1,
+        io_ticks: 2000,
+        time_in_queue: 2300,
+    });
+    Snapshot {
+        timestamp: OffsetDateTime::from_unix_timestamp(1700000000).unwrap(),
+        cpu_load: Some(Ok(vec![
+            CPULoad { user: 0.25, nice: 0.0, system: 0.25, interrupt: 0.0, idle: 0.5, platform: PlatformCpuLoad::zero() },
+            CPULoad { user: 0.5, nice: 0.0, system: 0.0, interrupt: 0.0, idle: 0.5, platform: PlatformCpuLoad::zero() },
+        ])),
+        load_average: Ok(LoadAverage { one: 0.5, five: 0.25, fifteen: 0.125 }),
+        memory: Err(unsupported()),
+        swap: Err(unsupported()),
+        uptime: Ok(Duration::from_secs(3600)),
+        boot_time: Ok(OffsetDateTime::from_unix_timestamp(1699996400).unwrap()),
+        battery_life: Err(SnapshotError {
+            kind: SnapshotErrorKind::NotPresent,
+            message: "Missing battery".to_string(),
+            detail: Some("battery".to_string()),
+        }),
+        on_ac_power: Ok(true),
+        mounts: Ok(vec![Filesystem {
+            files: 10,
+            files_total: 100,
+            files_avail: 90,
+            free: ByteSize::kib(512),
+            avail: ByteSize::kib(256),
+            total: ByteSize::kib(1024),
+            name_max: 255,
+            fs_type: "ext4".to_string(),
+            fs_mounted_from: "/dev/sda1".to_string(),
+            fs_mounted_on: "/".to_string(),
+        }]),
+        block_device_statistics: Ok(block_devices),
+        networks: Ok(networks),
+        network_stats,
+        cpu_temp: Ok(45.5),
+        socket_stats: Ok(SocketStats {
+            tcp_sockets_in_use: 20,
+            tcp_sockets_orphaned: 1,
+            udp_sockets_in_use: 3,
+            tcp6_sockets_in_use: 4,
+            udp6_sockets_in_use: 5,
+        }),
+        os_info: Err(unsupported()),
+        users: Ok(Vec::new()),
+        hardware_info: Err(unsupported()),
+        kernel_limits: Err(unsupported()),
+        interrupts: Ok(vec![Interrupt {
+            kind: InterruptKind::Hardware,
+            irq: "LOC".to_string(),
+            per_cpu: vec![1000, 2000],
+            description: "Local timer interrupts".to_string(),
+        }]),
+    }
+}
@@ -0,0 +1,257 @@
+//! A flat representation of the data types, shared by the line-based encoders.
+
+use crate::data::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Float(f64),
+    Integer(u64),
+    /// A monotonically increasing total
+    Counter(u64),
+    Boolean(bool),
+}
+
+/// A set of values measured for one thing (e.g. a CPU or a network interface),
+/// identified by the measurement name and tags.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub measurement: &'static str,
+    pub tags: Vec<(&'static str, String)>,
+    pub fields: Vec<(&'static str, Value)>,
+}
+
+impl Point {
+    fn new(measurement: &'static str) -> Point {
+        Point { measurement, tags: Vec::new(), fields: Vec::new() }
+    }
+
+    fn tag(mut self, key: &'static str, value: &str) -> Point {
+        self.tags.push((key, value.to_string()));
+        self
+    }
+
+    fn field(mut self, key: &'static str, value: Value) -> Point {
+        self.fields.push((key, value));
+        self
+    }
+}
+
+/// Data types that can be flattened into points.
+pub trait ToPoints {
+    fn to_points(&self) -> Vec<Point>;
+}
+
+impl ToPoints for [CPULoad] {
+    fn to_points(&self) -> Vec<Point> {
+        self.iter()
+            .enumerate()
+            .map(|(i, cpu)| {
+                Point::new("cpu")
+                    .tag("cpu", &i.to_string())
+                    .field("user", Value::Float(cpu.user as f64))
+                    .field("nice", Value::Float(cpu.nice as f64))
+                    .field("system", Value::Float(cpu.system as f64))
+                    .field("interrupt", Value::Float(cpu.interrupt as f64))
+                    .field("idle", Value::Float(cpu.idle as f64))
+                    .field("other", Value::Float(cpu.platform.sum() as f64))
+            })
+            .collect()
+    }
+}
+
+impl ToPoints for LoadAverage {
+    fn to_points(&self) -> Vec<Point> {
+        vec![Point::new("system")
+            .field("load1", Value::Float(self.one as f64))
+            .field("load5", Value::Float(self.five as f64))
+            .field("load15", Value::Float(self.fifteen as f64))]
+    }
+}
+
+impl ToPoints for Memory {
+    fn to_points(&self) -> Vec<Point> {
+        vec![Point::new("mem")
+            .field("total", Value::Integer(self.total.as_u64()))
+            .field("free", Value::Integer(self.free.as_u64()))
+            .field("used", Value::Integer(saturating_sub_bytes(self.total, self.free).as_u64()))]
+    }
+}
+
+impl ToPoints for Swap {
+    fn to_points(&self) -> Vec<Point> {
+        vec![Point::new("swap")
+            .field("total", Value::Integer(self.total.as_u64()))
+            .field("free", Value::Integer(self.free.as_u64()))
+            .field("used", Value::Integer(saturating_sub_bytes(self.total, self.free).as_u64()))]
+    }
+}
+
+impl ToPoints for BatteryLife {
+    fn to_points(&self) -> Vec<Point> {
+        vec![Point::new("battery")
+            .field("remaining_capacity", Value::Float(self.remaining_capacity as f64))
+            .field("remaining_time", Value::Integer(self.remaining_time.as_secs()))]
+    }
+}
+
+impl ToPoints for [Filesystem] {
+    fn to_points(&self) -> Vec<Point> {
+        self.iter()
+            .map(|fs| {
+                Point::new("disk")
+                    .tag("device", &fs.fs_mounted_from)
+                    .tag("fstype", &fs.fs_type)
+                    .tag("path", &fs.fs_mounted_on)
+                    .field("total", Value::Integer(fs.total.as_u64()))
+                    .field("free", Value::Integer(fs.free.as_u64()))
+                    .field("avail", Value::Integer(fs.avail.as_u64()))
+                    .field("used", Value::Integer(saturating_sub_bytes(fs.total, fs.free).as_u64()))
+                    .field("inodes_total", Value::Integer(fs.files_total as u64))
+                    .field("inodes_free", Value::Integer(fs.files_avail as u64))
+                    .field("inodes_used", Value::Integer(fs.files as u64))
+            })
+            .collect()
+    }
+}
+
+impl ToPoints for BTreeMap<String, BlockDeviceStats> {
+    fn to_points(&self) -> Vec<Point> {
+        self.values()
+            .map(|disk| {
+                // Sectors are always 512 bytes in /proc/diskstats
+                Point::new("diskio")
+                    .tag("name", &disk.name)
+                    .field("reads", Value::Counter(disk.read_ios as u64))
+                    .field("writes", Value::Counter(disk.write_ios as u64))
+                    .field("read_bytes", Value::Counter(disk.read_sectors as u64 * 512))
+                    .field("write_bytes", Value::Counter(disk.write_sectors as u64 * 512))
+                    .field("read_time", Value::Counter(disk.read_ticks as u64))
+                    .field("write_time", Value::Counter(disk.write_ticks as u64))
+                    .field("io_time", Value::Counter(disk.io_ticks as u64))
+                    .field("weighted_io_time", Value::Counter(disk.time_in_queue as u64))
+                    .field("iops_in_progress", Value::Integer(disk.in_flight as u64))
+            })
+            .collect()
+    }
+}
+
+impl ToPoints for BTreeMap<String, NetworkStats> {
+    fn to_points(&self) -> Vec<Point> {
+        self.iter().map(|(interface, stats)| network_point(interface, stats)).collect()
+    }
+}
+
+fn network_point(interface: &str, stats: &NetworkStats) -> Point {
+    Point::new("net")
+        .tag("interface", interface)
+        .field("bytes_recv", Value::Counter(stats.rx_bytes.as_u64()))
+        .field("bytes_sent", Value::Counter(stats.tx_bytes.as_u64()))
+        .field("packets_recv", Value::Counter(stats.rx_packets))
+        .field("packets_sent", Value::Counter(stats.tx_packets))
+        .field("err_in", Value::Counter(stats.rx_errors))
+        .field("err_out", Value::Counter(stats.tx_errors))
+}
+
+impl ToPoints for SocketStats {
+    fn to_points(&self) -> Vec<Point> {
+        vec![Point::new("sockets")
+            .field("tcp", Value::Integer(self.tcp_sockets_in_use as u64))
+            .field("tcp_orphaned", Value::Integer(self.tcp_sockets_orphaned as u64))
+            .field("udp", Value::Integer(self.udp_sockets_in_use as u64))
+            .field("tcp6", Value::Integer(self.tcp6_sockets_in_use as u64))
+            .field("udp6", Value::Integer(self.udp6_sockets_in_use as u64))]
+    }
+}
+
+impl ToPoints for KernelLimits {
+    fn to_points(&self) -> Vec<Point> {
+        vec![Point::new("kernel")
+            .field("file_handles", Value::Integer(self.file_handles))
+            .field("file_handles_max", Value::Integer(self.file_handles_max))
+            .field("inodes", Value::Integer(self.inodes))
+            .field("inodes_free", Value::Integer(self.inodes_free))
+            .field("pids", Value::Integer(self.pids))
+            .field("pid_max", Value::Integer(self.pid_max))
+            .field("threads_max", Value::Integer(self.threads_max))
+            .field("entropy_avail", Value::Integer(self.entropy_avail))]
+    }
+}
+
+impl ToPoints for [Interrupt] {
+    fn to_points(&self) -> Vec<Point> {
+        self.iter()
+            .flat_map(|irq| {
+                let kind = match irq.kind {
+                    InterruptKind::Hardware => "hardware",
+                    InterruptKind::Soft => "soft",
+                };
+                irq.per_cpu.iter().enumerate().map(move |(cpu, count)| {
+                    Point::new("interrupts")
+                        .tag("cpu", &cpu.to_string())
+                        .tag("irq", &irq.irq)
+                        .tag("kind", kind)
+                        .field("count", Value::Counter(*count))
+                })
+            })
+            .collect()
+    }
+}
+
+impl ToPoints for Snapshot {
+    /// Flattens all the metrics that were successfully collected.
+    fn to_points(&self) -> Vec<Point> {
+        let mut points = Vec::new();
+        if let Some(Ok(cpus)) = &self.cpu_load {
+            points.extend(cpus.to_points());
+        }
+        if let Ok(load) = &self.load_average {
+            points.extend(load.to_points());
+        }
+        let mut system = Point::new("system");
+        if let Ok(uptime) = &self.uptime {
+            system = system.field("uptime", Value::Integer(uptime.as_secs()));
+        }
+        if let Ok(users) = &self.users {
+            system = system.field("n_users", Value::Integer(users.len() as u64));
+        }
+        if !system.fields.is_empty() {
+            points.push(system);
+        }
+        if let Ok(mem) = &self.memory {
+            points.extend(mem.to_points());
+        }
+        if let Ok(swap) = &self.swap {
+            points.extend(swap.to_points());
+        }
+        if let Ok(battery) = &self.battery_life {
+            points.extend(battery.to_points());
+        }
+        if let Ok(on_ac) = &self.on_ac_power {
+            points.push(Point::new("power").field("on_ac", Value::Boolean(*on_ac)));
+        }
+        if let Ok(mounts) = &self.mounts {
+            points.extend(mounts.to_points());
+        }
+        if let Ok(disks) = &self.block_device_statistics {
+            points.extend(disks.to_points());
+        }
+        for (interface, stats) in self.network_stats.iter() {
+            if let Ok(stats) = stats {
+                points.push(network_point(interface, stats));
+            }
+        }
+        if let Ok(temp) = &self.cpu_temp {
+            points.push(Point::new("temp").field("cpu_celsius", Value::Float(*temp as f64)));
+        }
+        if let Ok(sockets) = &self.socket_stats {
+            points.extend(sockets.to_points());
+        }
+        if let Ok(limits) = &self.kernel_limits {
+            points.extend(limits.to_points());
+        }
+        if let Ok(interrupts) = &self.interrupts {
+            points.extend(interrupts.to_points());
+        }
+        points
+    }
+}
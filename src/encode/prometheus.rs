@@ -0,0 +1,311 @@
+//! Encoder for the Prometheus text exposition format.
+
+use crate::data::*;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetricType {
+    Counter,
+    Gauge,
+}
+
+/// A metric family: all the samples of a metric, with their labels
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    metric_type: MetricType,
+    samples: Vec<(Vec<(&'static str, String)>, f64)>,
+}
+
+impl Family {
+    fn new(name: &'static str, help: &'static str, metric_type: MetricType) -> Family {
+        Family { name, help, metric_type, samples: Vec::new() }
+    }
+
+    fn counter(name: &'static str, help: &'static str) -> Family {
+        Family::new(name, help, MetricType::Counter)
+    }
+
+    fn gauge(name: &'static str, help: &'static str) -> Family {
+        Family::new(name, help, MetricType::Gauge)
+    }
+
+    fn sample(mut self, labels: Vec<(&'static str, String)>, value: f64) -> Family {
+        self.samples.push((labels, value));
+        self
+    }
+
+    fn write(&self, out: &mut String) {
+        if self.samples.is_empty() {
+            return;
+        }
+        let metric_type = match self.metric_type {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        };
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} {}", self.name, metric_type);
+        for (labels, value) in self.samples.iter() {
+            out.push_str(self.name);
+            if !labels.is_empty() {
+                out.push('{');
+                for (i, (key, val)) in labels.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    let _ = write!(out, "{}=\"{}\"", key, escape_label(val));
+                }
+                out.push('}');
+            }
+            let _ = writeln!(out, " {}", format_value(*value));
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Renders a snapshot in the Prometheus text exposition format.
+///
+/// Metrics that are missing from the snapshot are skipped.
+pub fn encode(snapshot: &Snapshot) -> String {
+    let mut families = Vec::new();
+
+    if let Some(Ok(cpus)) = &snapshot.cpu_load {
+        let mut family = Family::gauge(
+            "systemstat_cpu_load_ratio",
+            "Fraction of time each CPU spent in each mode during the measurement.",
+        );
+        for (i, cpu) in cpus.iter().enumerate() {
+            for (mode, value) in [
+                ("user", cpu.user),
+                ("nice", cpu.nice),
+                ("system", cpu.system),
+                ("interrupt", cpu.interrupt),
+                ("idle", cpu.idle),
+                ("other", cpu.platform.sum()),
+            ] {
+                family = family.sample(vec![("cpu", i.to_string()), ("mode", mode.to_string())], value as f64);
+            }
+        }
+        families.push(family);
+    }
+
+    if let Ok(load) = &snapshot.load_average {
+        families.push(Family::gauge("systemstat_load1", "1 minute load average.").sample(vec![], load.one as f64));
+        families.push(Family::gauge("systemstat_load5", "5 minute load average.").sample(vec![], load.five as f64));
+        families.push(Family::gauge("systemstat_load15", "15 minute load average.").sample(vec![], load.fifteen as f64));
+    }
+
+    if let Ok(mem) = &snapshot.memory {
+        families.push(Family::gauge("systemstat_memory_total_bytes", "Total memory.")
+            .sample(vec![], mem.total.as_u64() as f64));
+        families.push(Family::gauge("systemstat_memory_free_bytes", "Free memory, including reclaimable caches.")
+            .sample(vec![], mem.free.as_u64() as f64));
+    }
+
+    if let Ok(swap) = &snapshot.swap {
+        families.push(Family::gauge("systemstat_swap_total_bytes", "Total swap space.")
+            .sample(vec![], swap.total.as_u64() as f64));
+        families.push(Family::gauge("systemstat_swap_free_bytes", "Free swap space.")
+            .sample(vec![], swap.free.as_u64() as f64));
+    }
+
+    if let Ok(uptime) = &snapshot.uptime {
+        families.push(Family::gauge("systemstat_uptime_seconds", "System uptime.")
+            .sample(vec![], uptime.as_secs_f64()));
+    }
+
+    if let Ok(boot_time) = &snapshot.boot_time {
+        families.push(Family::gauge("systemstat_boot_time_seconds", "System boot time, in seconds since the Unix epoch.")
+            .sample(vec![], boot_time.unix_timestamp() as f64));
+    }
+
+    if let Ok(battery) = &snapshot.battery_life {
+        families.push(Family::gauge("systemstat_battery_remaining_ratio", "Remaining battery capacity.")
+            .sample(vec![], battery.remaining_capacity as f64));
+        families.push(Family::gauge("systemstat_battery_remaining_seconds", "Remaining battery (dis)charge time.")
+            .sample(vec![], battery.remaining_time.as_secs_f64()));
+    }
+
+    if let Ok(on_ac) = &snapshot.on_ac_power {
+        families.push(Family::gauge("systemstat_on_ac_power", "Whether AC power is plugged in.")
+            .sample(vec![], if *on_ac { 1.0 } else { 0.0 }));
+    }
+
+    if let Ok(mounts) = &snapshot.mounts {
+        let labels = |fs: &Filesystem| vec![
+            ("device", fs.fs_mounted_from.clone()),
+            ("fstype", fs.fs_type.clone()),
+            ("mountpoint", fs.fs_mounted_on.clone()),
+        ];
+        let mut size = Family::gauge("systemstat_filesystem_size_bytes", "Filesystem size.");
+        let mut free = Family::gauge("systemstat_filesystem_free_bytes", "Free filesystem space.");
+        let mut avail = Family::gauge("systemstat_filesystem_avail_bytes", "Filesystem space available to non-root users.");
+        let mut files = Family::gauge("systemstat_filesystem_files", "Total file nodes in the filesystem.");
+        let mut files_free = Family::gauge("systemstat_filesystem_files_free", "Free file nodes in the filesystem.");
+        for fs in mounts.iter() {
+            size = size.sample(labels(fs), fs.total.as_u64() as f64);
+            free = free.sample(labels(fs), fs.free.as_u64() as f64);
+            avail = avail.sample(labels(fs), fs.avail.as_u64() as f64);
+            files = files.sample(labels(fs), fs.files_total as f64);
+            files_free = files_free.sample(labels(fs), fs.files_avail as f64);
+        }
+        families.extend(vec![size, free, avail, files, files_free]);
+    }
+
+    if let Ok(disks) = &snapshot.block_device_statistics {
+        let mut reads = Family::counter("systemstat_disk_reads_completed_total", "Reads completed successfully.");
+        let mut reads_merged = Family::counter("systemstat_disk_reads_merged_total", "Reads merged.");
+        let mut read_bytes = Family::counter("systemstat_disk_read_bytes_total", "Bytes read.");
+        let mut read_time = Family::counter("systemstat_disk_read_time_seconds_total", "Time spent reading.");
+        let mut writes = Family::counter("systemstat_disk_writes_completed_total", "Writes completed successfully.");
+        let mut writes_merged = Family::counter("systemstat_disk_writes_merged_total", "Writes merged.");
+        let mut written_bytes = Family::counter("systemstat_disk_written_bytes_total", "Bytes written.");
+        let mut write_time = Family::counter("systemstat_disk_write_time_seconds_total", "Time spent writing.");
+        let mut io_now = Family::gauge("systemstat_disk_io_now", "I/Os currently in progress.");
+        let mut io_time = Family::counter("systemstat_disk_io_time_seconds_total", "Time spent doing I/Os.");
+        let mut io_time_weighted = Family::counter("systemstat_disk_io_time_weighted_seconds_total", "Weighted time spent doing I/Os.");
+        for disk in disks.values() {
+            let labels = || vec![("device", disk.name.clone())];
+            // Sectors are always 512 bytes and ticks are milliseconds in /proc/diskstats
+            reads = reads.sample(labels(), disk.read_ios as f64);
+            reads_merged = reads_merged.sample(labels(), disk.read_merges as f64);
+            read_bytes = read_bytes.sample(labels(), disk.read_sectors as f64 * 512.0);
+            read_time = read_time.sample(labels(), disk.read_ticks as f64 / 1000.0);
+            writes = writes.sample(labels(), disk.write_ios as f64);
+            writes_merged = writes_merged.sample(labels(), disk.write_merges as f64);
+            written_bytes = written_bytes.sample(labels(), disk.write_sectors as f64 * 512.0);
+            write_time = write_time.sample(labels(), disk.write_ticks as f64 / 1000.0);
+            io_now = io_now.sample(labels(), disk.in_flight as f64);
+            io_time = io_time.sample(labels(), disk.io_ticks as f64 / 1000.0);
+            io_time_weighted = io_time_weighted.sample(labels(), disk.time_in_queue as f64 / 1000.0);
+        }
+        families.extend(vec![
+            reads, reads_merged, read_bytes, read_time,
+            writes, writes_merged, written_bytes, write_time,
+            io_now, io_time, io_time_weighted,
+        ]);
+    }
+
+    let mut rx_bytes = Family::counter("systemstat_network_receive_bytes_total", "Bytes received.");
+    let mut tx_bytes = Family::counter("systemstat_network_transmit_bytes_total", "Bytes transmitted.");
+    let mut rx_packets = Family::counter("systemstat_network_receive_packets_total", "Packets received.");
+    let mut tx_packets = Family::counter("systemstat_network_transmit_packets_total", "Packets transmitted.");
+    let mut rx_errors = Family::counter("systemstat_network_receive_errors_total", "Receive errors.");
+    let mut tx_errors = Family::counter("systemstat_network_transmit_errors_total", "Transmit errors.");
+    for (interface, stats) in snapshot.network_stats.iter() {
+        if let Ok(stats) = stats {
+            let labels = || vec![("interface", interface.clone())];
+            rx_bytes = rx_bytes.sample(labels(), stats.rx_bytes.as_u64() as f64);
+            tx_bytes = tx_bytes.sample(labels(), stats.tx_bytes.as_u64() as f64);
+            rx_packets = rx_packets.sample(labels(), stats.rx_packets as f64);
+            tx_packets = tx_packets.sample(labels(), stats.tx_packets as f64);
+            rx_errors = rx_errors.sample(labels(), stats.rx_errors as f64);
+            tx_errors = tx_errors.sample(labels(), stats.tx_errors as f64);
+        }
+    }
+    families.extend(vec![rx_bytes, tx_bytes, rx_packets, tx_packets, rx_errors, tx_errors]);
+
+    if let Ok(temp) = &snapshot.cpu_temp {
+        families.push(Family::gauge("systemstat_cpu_temperature_celsius", "CPU temperature.")
+            .sample(vec![], *temp as f64));
+    }
+
+    if let Ok(sockets) = &snapshot.socket_stats {
+        let protocol = |p: &str| vec![("protocol", p.to_string())];
+        families.push(Family::gauge("systemstat_sockets_in_use", "Sockets in use.")
+            .sample(protocol("tcp"), sockets.tcp_sockets_in_use as f64)
+            .sample(protocol("udp"), sockets.udp_sockets_in_use as f64)
+            .sample(protocol("tcp6"), sockets.tcp6_sockets_in_use as f64)
+            .sample(protocol("udp6"), sockets.udp6_sockets_in_use as f64));
+        families.push(Family::gauge("systemstat_tcp_sockets_orphaned", "Orphaned TCP sockets.")
+            .sample(vec![], sockets.tcp_sockets_orphaned as f64));
+    }
+
+    if let Ok(users) = &snapshot.users {
+        families.push(Family::gauge("systemstat_user_sessions", "Logged-in user sessions.")
+            .sample(vec![], users.len() as f64));
+    }
+
+    if let Ok(limits) = &snapshot.kernel_limits {
+        families.push(Family::gauge("systemstat_file_handles_allocated", "Allocated file handles.")
+            .sample(vec![], limits.file_handles as f64));
+        families.push(Family::gauge("systemstat_file_handles_maximum", "Maximum number of file handles.")
+            .sample(vec![], limits.file_handles_max as f64));
+        families.push(Family::gauge("systemstat_inodes_allocated", "Allocated inodes.")
+            .sample(vec![], limits.inodes as f64));
+        families.push(Family::gauge("systemstat_inodes_free", "Allocated but unused inodes.")
+            .sample(vec![], limits.inodes_free as f64));
+        families.push(Family::gauge("systemstat_pids", "PIDs in use by processes and threads.")
+            .sample(vec![], limits.pids as f64));
+        families.push(Family::gauge("systemstat_pid_max", "Largest PID the kernel will allocate.")
+            .sample(vec![], limits.pid_max as f64));
+        families.push(Family::gauge("systemstat_threads_maximum", "Maximum number of threads.")
+            .sample(vec![], limits.threads_max as f64));
+        families.push(Family::gauge("systemstat_entropy_available_bits", "Available entropy.")
+            .sample(vec![], limits.entropy_avail as f64));
+    }
+
+    if let Ok(interrupts) = &snapshot.interrupts {
+        let mut family = Family::counter("systemstat_interrupts_total", "Interrupts serviced, per CPU.");
+        for irq in interrupts.iter() {
+            let kind = match irq.kind {
+                InterruptKind::Hardware => "hardware",
+                InterruptKind::Soft => "soft",
+            };
+            for (cpu, count) in irq.per_cpu.iter().enumerate() {
+                family = family.sample(vec![
+                    ("cpu", cpu.to_string()),
+                    ("irq", irq.irq.clone()),
+                    ("kind", kind.to_string()),
+                ], *count as f64);
+            }
+        }
+        families.push(family);
+    }
+
+    let mut out = String::new();
+    for family in families.iter() {
+        family.write(&mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let out = encode(&crate::encode::test_snapshot());
+        assert!(out.contains("# HELP systemstat_load1 1 minute load average.\n# TYPE systemstat_load1 gauge\nsystemstat_load1 0.5\n"));
+        assert!(out.contains("systemstat_cpu_load_ratio{cpu=\"1\",mode=\"user\"} 0.5\n"));
+        assert!(out.contains("# TYPE systemstat_disk_read_bytes_total counter\nsystemstat_disk_read_bytes_total{device=\"sda\"} 409600\n"));
+        assert!(out.contains("systemstat_disk_io_time_seconds_total{device=\"sda\"} 2\n"));
+        assert!(out.contains("systemstat_network_receive_bytes_total{interface=\"eth0\"} 1000\n"));
+        assert!(out.contains("systemstat_filesystem_size_bytes{device=\"/dev/sda1\",fstype=\"ext4\",mountpoint=\"/\"} 1048576\n"));
+        assert!(out.contains("systemstat_interrupts_total{cpu=\"1\",irq=\"LOC\",kind=\"hardware\"} 2000\n"));
+        assert!(out.contains("systemstat_on_ac_power 1\n"));
+        assert!(!out.contains("systemstat_memory"));
+        assert!(!out.contains("systemstat_battery"));
+        // Every family is declared exactly once
+        assert_eq!(out.matches("# TYPE systemstat_sockets_in_use ").count(), 1);
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
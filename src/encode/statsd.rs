@@ -0,0 +1,124 @@
+//! Encoder for StatsD gauges and counters.
+
+use super::points::*;
+use crate::data::BTreeMap;
+use std::fmt::Write;
+
+/// Encodes values as StatsD lines, named `prefix.measurement.tag_values.field`.
+///
+/// Since StatsD counters are increments, counters are sent as the difference from the previous
+/// call to `encode`, so the encoder has to be kept around between calls. Counters are skipped
+/// the first time they're seen, and forgotten when a call doesn't include them (e.g. when
+/// a network interface goes away). Floats that aren't finite are skipped.
+pub struct Encoder {
+    prefix: String,
+    previous: BTreeMap<String, u64>,
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+impl Encoder {
+    /// Creates an encoder with a name prefix (e.g. `systemstat` or `systemstat.hostname`).
+    pub fn new(prefix: &str) -> Encoder {
+        Encoder {
+            prefix: prefix.to_string(),
+            previous: BTreeMap::new(),
+        }
+    }
+
+    /// Encodes a value as StatsD lines, one line per field.
+    pub fn encode<T: ToPoints + ?Sized>(&mut self, value: &T) -> String {
+        let mut out = String::new();
+        let mut seen = BTreeMap::new();
+        for point in value.to_points() {
+            let mut name = String::new();
+            if !self.prefix.is_empty() {
+                name.push_str(&self.prefix);
+                name.push('.');
+            }
+            name.push_str(point.measurement);
+            for (_, value) in point.tags.iter() {
+                name.push('.');
+                name.push_str(&sanitize(value));
+            }
+            for (key, value) in point.fields.iter() {
+                let metric = format!("{}.{}", name, key);
+                let _ = match *value {
+                    Value::Float(v) if !v.is_finite() => Ok(()),
+                    Value::Float(v) => writeln!(out, "{}:{}|g", metric, v),
+                    Value::Integer(v) => writeln!(out, "{}:{}|g", metric, v),
+                    Value::Boolean(v) => writeln!(out, "{}:{}|g", metric, v as u8),
+                    Value::Counter(v) => {
+                        let prev = self.previous.get(&metric).copied();
+                        seen.insert(metric.clone(), v);
+                        match prev {
+                            // After a counter reset, everything since the reset is new
+                            Some(prev) => writeln!(out, "{}:{}|c", metric, if v >= prev { v - prev } else { v }),
+                            None => Ok(()),
+                        }
+                    }
+                };
+            }
+        }
+        self.previous = seen;
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::*;
+
+    #[test]
+    fn test_encode() {
+        let mut snapshot = crate::encode::test_snapshot();
+        let mut encoder = Encoder::new("systemstat.web1");
+        let out = encoder.encode(&snapshot);
+        assert!(out.contains("systemstat.web1.cpu.1.user:0.5|g\n"));
+        assert!(out.contains("systemstat.web1.system.load15:0.125|g\n"));
+        assert!(out.contains("systemstat.web1.disk._dev_sda1.ext4._.total:1048576|g\n"));
+        assert!(out.contains("systemstat.web1.power.on_ac:1|g\n"));
+        assert!(!out.contains("|c"));
+
+        if let Some(Ok(stats)) = snapshot.network_stats.get_mut("eth0") {
+            stats.rx_bytes = ByteSize::b(1500);
+            stats.tx_errors = 0;
+        }
+        let out = encoder.encode(&snapshot);
+        assert!(out.contains("systemstat.web1.net.eth0.bytes_recv:500|c\n"));
+        assert!(out.contains("systemstat.web1.net.eth0.err_out:0|c\n"));
+        assert!(out.contains("systemstat.web1.interrupts.0.LOC.hardware.count:0|c\n"));
+
+        // Counters of an interface that went away are forgotten
+        let stats = snapshot.network_stats.remove("eth0").unwrap();
+        encoder.encode(&snapshot);
+        assert!(!encoder.previous.keys().any(|metric| metric.contains(".net.eth0.")));
+        snapshot.network_stats.insert("eth0".to_string(), stats);
+        assert!(!encoder.encode(&snapshot).contains(".net.eth0."));
+    }
+
+    #[test]
+    fn test_encode_non_finite() {
+        let load = LoadAverage { one: f32::NAN, five: 1.0, fifteen: f32::INFINITY };
+        assert_eq!(Encoder::new("").encode(&load), "system.load5:1|g\n");
+    }
+
+    #[test]
+    fn test_encode_data_type() {
+        let sockets = SocketStats {
+            tcp_sockets_in_use: 1,
+            tcp_sockets_orphaned: 0,
+            udp_sockets_in_use: 2,
+            tcp6_sockets_in_use: 3,
+            udp6_sockets_in_use: 4,
+        };
+        let out = Encoder::new("").encode(&sockets);
+        assert_eq!(out, "sockets.tcp:1|g\nsockets.tcp_orphaned:0|g\nsockets.udp:2|g\nsockets.tcp6:3|g\nsockets.udp6:4|g\n");
+    }
+}
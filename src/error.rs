@@ -0,0 +1,108 @@
+//! This module provides the error type that describes why some information could not be obtained.
+//!
+//! For compatibility, all `Platform` methods still return `io::Result`. The `io::Error`s
+//! created by this library carry an `Error` inside, which you can get back with `Error::from`:
+//!
+//! ```
+//! use systemstat::{Error, Platform, System};
+//!
+//! let sys = System::new();
+//! match sys.battery_life().map_err(Error::from) {
+//!     Ok(battery) => println!("Battery: {}%", battery.remaining_capacity * 100.0),
+//!     Err(Error::Unsupported) => println!("Battery: not supported on this platform"),
+//!     Err(Error::NotPresent(_)) => println!("Battery: none"),
+//!     Err(x) => println!("Battery: error: {}", x),
+//! }
+//! ```
+
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The information is not available on this platform.
+    Unsupported,
+    /// The information is not present on this system (e.g. there's no battery).
+    /// Contains a description of what's missing.
+    NotPresent(String),
+    /// A system file could not be parsed.
+    Parse {
+        path: String,
+        /// The (1-based) line where parsing failed, if known
+        line: Option<usize>,
+        message: String,
+    },
+    /// Any other I/O or OS error, e.g. a file is missing or permission was denied.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Unsupported => write!(f, "Not supported"),
+            Error::NotPresent(what) => write!(f, "Missing {}", what),
+            Error::Parse { path, line: Some(line), message } =>
+                write!(f, "Could not parse {} (line {}): {}", path, line, message),
+            Error::Parse { path, line: None, message } =>
+                write!(f, "Could not parse {}: {}", path, message),
+            Error::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    /// Wraps the error into an `io::Error` with a matching `ErrorKind`.
+    fn from(err: Error) -> io::Error {
+        let kind = match err {
+            Error::Unsupported => io::ErrorKind::Unsupported,
+            Error::NotPresent(_) => io::ErrorKind::NotFound,
+            Error::Parse { .. } => io::ErrorKind::InvalidData,
+            Error::Io(err) => return err,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+impl From<io::Error> for Error {
+    /// Unwraps an `Error` previously wrapped into an `io::Error`,
+    /// or wraps any other `io::Error` into `Error::Io`.
+    fn from(err: io::Error) -> Error {
+        if err.get_ref().map(|inner| inner.is::<Error>()).unwrap_or(false) {
+            *err.into_inner()
+                .expect("checked above")
+                .downcast::<Error>()
+                .expect("checked above")
+        } else {
+            Error::Io(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_round_trip() {
+        let err: io::Error = Error::NotPresent("battery information".to_string()).into();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "Missing battery information");
+        assert!(matches!(Error::from(err), Error::NotPresent(what) if what == "battery information"));
+
+        let err: io::Error = Error::Unsupported.into();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(matches!(Error::from(err), Error::Unsupported));
+
+        let err = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(matches!(Error::from(err), Error::Io(e) if e.kind() == io::ErrorKind::PermissionDenied));
+    }
+}
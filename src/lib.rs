@@ -15,9 +15,24 @@ extern crate lazy_static;
 #[cfg(feature = "serde")]
 extern crate the_serde as serde;
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
 pub mod data;
+pub mod encode;
+pub mod error;
 pub mod platform;
+pub mod processes;
+#[cfg(feature = "serde")]
+pub mod schema;
+pub mod watcher;
 
 pub use self::data::*;
+pub use self::error::Error;
 pub use self::platform::Platform;
+#[cfg(feature = "testing")]
+pub use self::platform::FakePlatform;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::platform::linux::Poller;
 pub use self::platform::PlatformImpl as System;
+pub use self::processes::{ProcessTree, ResourceUsage};
+pub use self::watcher::Watcher;
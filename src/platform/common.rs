@@ -1,11 +1,19 @@
-use std::{io, path, convert::{TryFrom, TryInto}};
+use std::{io, path, thread, time::Instant, convert::{TryFrom, TryInto}};
 use crate::data::*;
+use crate::error::Error;
 
 /// The Platform trait declares all the functions for getting system information.
 ///
+/// All functions return `io::Result` for compatibility. Use `Error::from` on the `io::Error`
+/// to find out whether the information is unsupported, not present or unparseable.
+///
 /// NOTE: any impl MUST override one of `uptime` or `boot_time`.
+///
+/// The trait can be used as a trait object (`Box<dyn Platform>`), e.g. to substitute
+/// a fake implementation in tests. `mount_at` is not available on trait objects,
+/// use `mount_at_path` instead.
 pub trait Platform {
-    fn new() -> Self;
+    fn new() -> Self where Self: Sized;
 
     /// Returns a delayed vector of CPU load statistics, one object per CPU (core).
     ///
@@ -18,13 +26,11 @@ pub trait Platform {
     /// You need to wait some time (about a second is good) before unwrapping the
     /// `DelayedMeasurement` with `.done()`.
     fn cpu_load_aggregate(&self) -> io::Result<DelayedMeasurement<CPULoad>> {
-        let measurement = self.cpu_load()?;
-        Ok(DelayedMeasurement::new(
-                Box::new(move || measurement.done().map(|ls| {
-                    let mut it = ls.iter();
-                    let first = it.next().unwrap().clone(); // has to be a variable, rust moves the iterator otherwise
-                    it.fold(first, |acc, l| acc.avg_add(l))
-                }))))
+        Ok(self.cpu_load()?.map(|ls| {
+            let mut it = ls.iter();
+            let first = it.next().unwrap().clone(); // has to be a variable, rust moves the iterator otherwise
+            it.fold(first, |acc, l| acc.avg_add(l))
+        }))
     }
 
     /// Returns a load average object.
@@ -51,7 +57,7 @@ pub trait Platform {
         self.boot_time().and_then(|bt| {
             (OffsetDateTime::now_utc() - bt)
                 .try_into()
-                .map_err(|_| io::Error::new(io::ErrorKind::Other, "Could not process time"))
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Could not process time"))
         })
     }
 
@@ -60,7 +66,7 @@ pub trait Platform {
         self.uptime().and_then(|ut| {
             Ok(OffsetDateTime::now_utc()
                 - time::Duration::try_from(ut)
-                    .map_err(|_| io::Error::new(io::ErrorKind::Other, "Could not process time"))?)
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Could not process time"))?)
         })
     }
 
@@ -74,19 +80,47 @@ pub trait Platform {
     fn mounts(&self) -> io::Result<Vec<Filesystem>>;
 
     /// Returns a filesystem mount information object for the filesystem at a given path.
-    fn mount_at<P: AsRef<path::Path>>(&self, path: P) -> io::Result<Filesystem> {
+    fn mount_at<P: AsRef<path::Path>>(&self, path: P) -> io::Result<Filesystem> where Self: Sized {
+        self.mount_at_path(path.as_ref())
+    }
+
+    /// Same as `mount_at`, but usable on trait objects.
+    fn mount_at_path(&self, path: &path::Path) -> io::Result<Filesystem> {
         self.mounts()
             .and_then(|mounts| {
                 mounts
                     .into_iter()
-                    .find(|mount| path::Path::new(&mount.fs_mounted_on) == path.as_ref())
-                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No such mount"))
+                    .find(|mount| path::Path::new(&mount.fs_mounted_on) == path)
+                    .ok_or_else(|| Error::NotPresent("mount".to_string()).into())
         })
     }
 
     /// Returns a map of block device statistics objects
     fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>>;
 
+    /// Same as `block_device_statistics`, but only for physical disks: partitions,
+    /// device-mapper and md devices, loop devices and RAM disks are left out, so that
+    /// adding up the statistics counts each I/O once.
+    fn disk_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
+        Ok(self
+            .block_device_statistics()?
+            .into_iter()
+            .filter(|(_, stats)| stats.kind.is_physical_disk())
+            .collect())
+    }
+
+    /// Returns the statistics of all physical disks added up (see `disk_statistics`).
+    fn disk_statistics_aggregate(&self) -> io::Result<BlockDeviceStats> {
+        Ok(BlockDeviceStats::sum("total", self.disk_statistics()?.values()))
+    }
+
+    /// Returns a delayed measurement of iostat-style metrics (utilization, throughput, latency)
+    /// for each block device.
+    ///
+    /// You need to wait some time (about a second is good) before unwrapping the
+    /// `DelayedMeasurement` with `.done()`.
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>>;
+
     /// Returns a map of network intefrace information objects.
     ///
     /// It's a map because most operating systems return an object per IP address, not per
@@ -97,6 +131,10 @@ pub trait Platform {
     /// Returns statistics for a given interface (bytes/packets sent/received)
     fn network_stats(&self, interface: &str) -> io::Result<NetworkStats>;
 
+    /// Returns the link quality, signal level and error counters of the wireless interfaces,
+    /// by interface name.
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>>;
+
     /// Returns the current CPU temperature in degrees Celsius.
     ///
     /// Depending on the platform, this might be core 0, package, etc.
@@ -104,4 +142,123 @@ pub trait Platform {
 
     /// Returns information about the number of sockets in use
     fn socket_stats(&self) -> io::Result<SocketStats>;
+
+    /// Returns information about the operating system: hostname, kernel and architecture.
+    ///
+    /// On Linux, this also includes the distribution (from os-release) and the detected
+    /// virtualization or container technology.
+    fn os_info(&self) -> io::Result<OsInfo>;
+
+    /// Returns a vector of the currently logged-in user sessions, from the utmp(x) database.
+    fn users(&self) -> io::Result<Vec<UserSession>>;
+
+    /// Returns the hardware identification (vendor, product, serial, BIOS, etc.) from DMI/SMBIOS.
+    fn hardware_info(&self) -> io::Result<HardwareInfo>;
+
+    /// Returns the usage and limits of kernel tables (file handles, inodes, PIDs) and the entropy pool.
+    fn kernel_limits(&self) -> io::Result<KernelLimits>;
+
+    /// Returns a vector of per-CPU hardware interrupt and softirq counters.
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>>;
+
+    /// Returns a delayed vector of per-CPU hardware interrupt and softirq rates (per second).
+    ///
+    /// You need to wait some time (about a second is good) before unwrapping the
+    /// `DelayedMeasurement` with `.done()`.
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>>;
+
+    /// Returns which CPUs the current process can use: the online CPUs, its affinity mask
+    /// and the CPU quota of its cgroup.
+    ///
+    /// Unlike the number of CPUs, `CpuAvailability::parallelism` takes containers into account,
+    /// which makes it suitable for sizing thread pools.
+    fn cpu_availability(&self) -> io::Result<CpuAvailability>;
+
+    /// Same as `cpu_load`, but only for the CPUs in the affinity mask of the current process
+    /// (see `cpu_availability`).
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>>;
+
+    /// Returns a vector of all the running processes.
+    ///
+    /// Use `ProcessTree` to sum up their resource usage by subtree, user or control group.
+    fn processes(&self) -> io::Result<Vec<Process>>;
+
+    /// Returns the threads of a process.
+    fn threads(&self, pid: u32) -> io::Result<Vec<Thread>>;
+
+    /// Returns a delayed per-thread CPU load measurement for a process, e.g. to find
+    /// which thread of a busy process is using the CPU.
+    ///
+    /// You need to wait some time (about a second is good) before unwrapping the
+    /// `DelayedMeasurement` with `.done()`.
+    fn thread_load(&self, pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>>;
+
+    /// Returns the number of open file descriptors of a process, by type.
+    ///
+    /// This usually requires running as the same user as the process (or as root),
+    /// and so do `process_io` and `process_memory`.
+    fn process_fds(&self, pid: u32) -> io::Result<ProcessFds>;
+
+    /// Returns the I/O done by a process since it started.
+    fn process_io(&self, pid: u32) -> io::Result<ProcessIo>;
+
+    /// Returns the memory used by a process, including the proportional (PSS)
+    /// and unique (USS) set sizes.
+    fn process_memory(&self, pid: u32) -> io::Result<ProcessMemory>;
+
+    /// Returns the kinds of information that can be obtained on this platform.
+    ///
+    /// On Linux, this depends on the environment: e.g. most information is unavailable
+    /// without procfs, and there's no battery information on machines without a battery.
+    fn capabilities(&self) -> Capabilities;
+
+    /// Returns a snapshot of most of the information available through this trait (see `Snapshot`).
+    ///
+    /// If the CPU load is measured, this blocks for `options.cpu_load_duration`.
+    fn snapshot(&self, options: &SnapshotOptions) -> Snapshot {
+        let timestamp = OffsetDateTime::now_utc();
+        let started = Instant::now();
+        let cpu_load = options.cpu_load_duration.map(|duration| (duration, self.cpu_load()));
+        let networks = self.networks();
+        let network_stats = match (&networks, options.network_stats) {
+            (Ok(networks), true) => networks
+                .keys()
+                .map(|name| (name.clone(), self.network_stats(name).map_err(SnapshotError::from)))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+        let mut snapshot = Snapshot {
+            timestamp,
+            cpu_load: None,
+            load_average: self.load_average().map_err(SnapshotError::from),
+            memory: self.memory().map_err(SnapshotError::from),
+            swap: self.swap().map_err(SnapshotError::from),
+            uptime: self.uptime().map_err(SnapshotError::from),
+            boot_time: self.boot_time().map_err(SnapshotError::from),
+            battery_life: self.battery_life().map_err(SnapshotError::from),
+            on_ac_power: self.on_ac_power().map_err(SnapshotError::from),
+            mounts: self.mounts().map_err(SnapshotError::from),
+            block_device_statistics: self.block_device_statistics().map_err(SnapshotError::from),
+            networks: networks.map_err(SnapshotError::from),
+            network_stats,
+            cpu_temp: self.cpu_temp().map_err(SnapshotError::from),
+            socket_stats: self.socket_stats().map_err(SnapshotError::from),
+            os_info: self.os_info().map_err(SnapshotError::from),
+            users: self.users().map_err(SnapshotError::from),
+            hardware_info: self.hardware_info().map_err(SnapshotError::from),
+            kernel_limits: self.kernel_limits().map_err(SnapshotError::from),
+            interrupts: self.interrupts().map_err(SnapshotError::from),
+        };
+        snapshot.cpu_load = cpu_load.map(|(duration, measurement)| {
+            measurement
+                .and_then(|measurement| {
+                    if let Some(remaining) = duration.checked_sub(started.elapsed()) {
+                        thread::sleep(remaining);
+                    }
+                    measurement.done()
+                })
+                .map_err(SnapshotError::from)
+        });
+        snapshot
+    }
 }
@@ -0,0 +1,512 @@
+//! A `Platform` that returns fixed values, for testing code that uses this library.
+//!
+//! Enabled by the `testing` feature.
+//!
+//! ```
+//! use systemstat::{Platform, FakePlatform, LoadAverage};
+//!
+//! let sys = FakePlatform::new()
+//!     .with_load_average(LoadAverage { one: 4.0, five: 2.0, fifteen: 1.0 });
+//! assert_eq!(sys.load_average().unwrap().one, 4.0);
+//! assert!(sys.memory().is_err());
+//! ```
+
+use std::io;
+use crate::data::*;
+use crate::error::Error;
+use super::common::*;
+
+/// An implementation of `Platform` that answers from a `Snapshot`.
+///
+/// Start with `FakePlatform::new()` (where everything is unsupported) and fill in values
+/// with the `with_*` methods, or use `from_snapshot` with a snapshot taken on a real system
+/// (and e.g. deserialized with the `serde` feature).
+/// `cpu_load` and the other delayed measurements return the stored values immediately.
+#[derive(Debug, Clone)]
+pub struct FakePlatform {
+    snapshot: Snapshot,
+    interrupt_rates: SnapshotResult<Vec<InterruptRate>>,
+    disk_io_rates: SnapshotResult<BTreeMap<String, DiskIoRates>>,
+    wireless: SnapshotResult<BTreeMap<String, WirelessStats>>,
+    cpu_availability: SnapshotResult<CpuAvailability>,
+    processes: SnapshotResult<Vec<Process>>,
+    threads: BTreeMap<u32, Vec<Thread>>,
+    thread_load: BTreeMap<u32, Vec<ThreadLoad>>,
+    process_fds: BTreeMap<u32, ProcessFds>,
+    process_io: BTreeMap<u32, ProcessIo>,
+    process_memory: BTreeMap<u32, ProcessMemory>,
+}
+
+fn unsupported() -> SnapshotError {
+    SnapshotError::from(io::Error::from(Error::Unsupported))
+}
+
+impl FakePlatform {
+    pub fn from_snapshot(snapshot: Snapshot) -> FakePlatform {
+        FakePlatform {
+            snapshot,
+            interrupt_rates: Err(unsupported()),
+            disk_io_rates: Err(unsupported()),
+            wireless: Err(unsupported()),
+            cpu_availability: Err(unsupported()),
+            processes: Err(unsupported()),
+            threads: BTreeMap::new(),
+            thread_load: BTreeMap::new(),
+            process_fds: BTreeMap::new(),
+            process_io: BTreeMap::new(),
+            process_memory: BTreeMap::new(),
+        }
+    }
+
+    pub fn as_snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// Sets the per-CPU loads. Like `/proc/stat` on Linux, they're for the CPUs in
+    /// `CpuAvailability::online` (see `with_cpu_availability`), in that order.
+    pub fn with_cpu_load(mut self, cpu_load: Vec<CPULoad>) -> FakePlatform {
+        self.snapshot.cpu_load = Some(Ok(cpu_load));
+        self
+    }
+
+    pub fn with_load_average(mut self, load_average: LoadAverage) -> FakePlatform {
+        self.snapshot.load_average = Ok(load_average);
+        self
+    }
+
+    pub fn with_memory(mut self, memory: Memory) -> FakePlatform {
+        self.snapshot.memory = Ok(memory);
+        self
+    }
+
+    pub fn with_swap(mut self, swap: Swap) -> FakePlatform {
+        self.snapshot.swap = Ok(swap);
+        self
+    }
+
+    /// Sets both the uptime and the boot time (relative to the snapshot timestamp).
+    pub fn with_uptime(mut self, uptime: Duration) -> FakePlatform {
+        self.snapshot.uptime = Ok(uptime);
+        self.snapshot.boot_time = Ok(self.snapshot.timestamp - uptime);
+        self
+    }
+
+    pub fn with_battery_life(mut self, battery_life: BatteryLife) -> FakePlatform {
+        self.snapshot.battery_life = Ok(battery_life);
+        self
+    }
+
+    pub fn with_on_ac_power(mut self, on_ac_power: bool) -> FakePlatform {
+        self.snapshot.on_ac_power = Ok(on_ac_power);
+        self
+    }
+
+    pub fn with_mounts(mut self, mounts: Vec<Filesystem>) -> FakePlatform {
+        self.snapshot.mounts = Ok(mounts);
+        self
+    }
+
+    pub fn with_block_device_statistics(mut self, stats: BTreeMap<String, BlockDeviceStats>) -> FakePlatform {
+        self.snapshot.block_device_statistics = Ok(stats);
+        self
+    }
+
+    pub fn with_disk_io_rates(mut self, rates: BTreeMap<String, DiskIoRates>) -> FakePlatform {
+        self.disk_io_rates = Ok(rates);
+        self
+    }
+
+    /// Adds a network interface, with its statistics.
+    pub fn with_network(mut self, network: Network, stats: NetworkStats) -> FakePlatform {
+        self.snapshot.network_stats.insert(network.name.clone(), Ok(stats));
+        let mut networks = self.snapshot.networks.unwrap_or_default();
+        networks.insert(network.name.clone(), network);
+        self.snapshot.networks = Ok(networks);
+        self
+    }
+
+    pub fn with_wireless(mut self, wireless: BTreeMap<String, WirelessStats>) -> FakePlatform {
+        self.wireless = Ok(wireless);
+        self
+    }
+
+    pub fn with_cpu_temp(mut self, cpu_temp: f32) -> FakePlatform {
+        self.snapshot.cpu_temp = Ok(cpu_temp);
+        self
+    }
+
+    pub fn with_socket_stats(mut self, socket_stats: SocketStats) -> FakePlatform {
+        self.snapshot.socket_stats = Ok(socket_stats);
+        self
+    }
+
+    pub fn with_os_info(mut self, os_info: OsInfo) -> FakePlatform {
+        self.snapshot.os_info = Ok(os_info);
+        self
+    }
+
+    pub fn with_users(mut self, users: Vec<UserSession>) -> FakePlatform {
+        self.snapshot.users = Ok(users);
+        self
+    }
+
+    pub fn with_hardware_info(mut self, hardware_info: HardwareInfo) -> FakePlatform {
+        self.snapshot.hardware_info = Ok(hardware_info);
+        self
+    }
+
+    pub fn with_kernel_limits(mut self, kernel_limits: KernelLimits) -> FakePlatform {
+        self.snapshot.kernel_limits = Ok(kernel_limits);
+        self
+    }
+
+    pub fn with_interrupts(mut self, interrupts: Vec<Interrupt>) -> FakePlatform {
+        self.snapshot.interrupts = Ok(interrupts);
+        self
+    }
+
+    pub fn with_interrupt_rates(mut self, interrupt_rates: Vec<InterruptRate>) -> FakePlatform {
+        self.interrupt_rates = Ok(interrupt_rates);
+        self
+    }
+
+    pub fn with_cpu_availability(mut self, cpu_availability: CpuAvailability) -> FakePlatform {
+        self.cpu_availability = Ok(cpu_availability);
+        self
+    }
+
+    pub fn with_processes(mut self, processes: Vec<Process>) -> FakePlatform {
+        self.processes = Ok(processes);
+        self
+    }
+
+    pub fn with_threads(mut self, pid: u32, threads: Vec<Thread>) -> FakePlatform {
+        self.threads.insert(pid, threads);
+        self
+    }
+
+    pub fn with_thread_load(mut self, pid: u32, thread_load: Vec<ThreadLoad>) -> FakePlatform {
+        self.thread_load.insert(pid, thread_load);
+        self
+    }
+
+    pub fn with_process_fds(mut self, pid: u32, fds: ProcessFds) -> FakePlatform {
+        self.process_fds.insert(pid, fds);
+        self
+    }
+
+    pub fn with_process_io(mut self, pid: u32, io: ProcessIo) -> FakePlatform {
+        self.process_io.insert(pid, io);
+        self
+    }
+
+    pub fn with_process_memory(mut self, pid: u32, memory: ProcessMemory) -> FakePlatform {
+        self.process_memory.insert(pid, memory);
+        self
+    }
+}
+
+impl Default for FakePlatform {
+    fn default() -> FakePlatform {
+        FakePlatform::new()
+    }
+}
+
+fn answer<T: Clone>(result: &SnapshotResult<T>) -> io::Result<T> {
+    result.clone().map_err(io::Error::from)
+}
+
+fn process<T: Clone>(processes: &BTreeMap<u32, T>, pid: u32) -> io::Result<T> {
+    processes
+        .get(&pid)
+        .cloned()
+        .ok_or_else(|| Error::NotPresent(format!("process {}", pid)).into())
+}
+
+fn delayed<T: Clone + Send + 'static>(result: &SnapshotResult<T>) -> io::Result<DelayedMeasurement<T>> {
+    let value = answer(result)?;
+    Ok(DelayedMeasurement::new(Box::new(move || Ok(value.clone()))))
+}
+
+impl Platform for FakePlatform {
+    /// Creates a platform where everything is unsupported.
+    fn new() -> FakePlatform {
+        FakePlatform::from_snapshot(Snapshot {
+            timestamp: OffsetDateTime::UNIX_EPOCH,
+            cpu_load: None,
+            load_average: Err(unsupported()),
+            memory: Err(unsupported()),
+            swap: Err(unsupported()),
+            uptime: Err(unsupported()),
+            boot_time: Err(unsupported()),
+            battery_life: Err(unsupported()),
+            on_ac_power: Err(unsupported()),
+            mounts: Err(unsupported()),
+            block_device_statistics: Err(unsupported()),
+            networks: Err(unsupported()),
+            network_stats: BTreeMap::new(),
+            cpu_temp: Err(unsupported()),
+            socket_stats: Err(unsupported()),
+            os_info: Err(unsupported()),
+            users: Err(unsupported()),
+            hardware_info: Err(unsupported()),
+            kernel_limits: Err(unsupported()),
+            interrupts: Err(unsupported()),
+        })
+    }
+
+    fn cpu_load(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        match &self.snapshot.cpu_load {
+            Some(cpu_load) => delayed(cpu_load),
+            None => Err(Error::Unsupported.into()),
+        }
+    }
+
+    fn load_average(&self) -> io::Result<LoadAverage> {
+        answer(&self.snapshot.load_average)
+    }
+
+    fn memory(&self) -> io::Result<Memory> {
+        answer(&self.snapshot.memory)
+    }
+
+    fn swap(&self) -> io::Result<Swap> {
+        answer(&self.snapshot.swap)
+    }
+
+    fn uptime(&self) -> io::Result<Duration> {
+        answer(&self.snapshot.uptime)
+    }
+
+    fn boot_time(&self) -> io::Result<OffsetDateTime> {
+        answer(&self.snapshot.boot_time)
+    }
+
+    fn battery_life(&self) -> io::Result<BatteryLife> {
+        answer(&self.snapshot.battery_life)
+    }
+
+    fn on_ac_power(&self) -> io::Result<bool> {
+        answer(&self.snapshot.on_ac_power)
+    }
+
+    fn mounts(&self) -> io::Result<Vec<Filesystem>> {
+        answer(&self.snapshot.mounts)
+    }
+
+    fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
+        answer(&self.snapshot.block_device_statistics)
+    }
+
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>> {
+        delayed(&self.disk_io_rates)
+    }
+
+    fn networks(&self) -> io::Result<BTreeMap<String, Network>> {
+        answer(&self.snapshot.networks)
+    }
+
+    fn network_stats(&self, interface: &str) -> io::Result<NetworkStats> {
+        match self.snapshot.network_stats.get(interface) {
+            Some(stats) => answer(stats),
+            None => Err(Error::NotPresent(format!("network interface {}", interface)).into()),
+        }
+    }
+
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>> {
+        answer(&self.wireless)
+    }
+
+    fn cpu_temp(&self) -> io::Result<f32> {
+        answer(&self.snapshot.cpu_temp)
+    }
+
+    fn socket_stats(&self) -> io::Result<SocketStats> {
+        answer(&self.snapshot.socket_stats)
+    }
+
+    fn os_info(&self) -> io::Result<OsInfo> {
+        answer(&self.snapshot.os_info)
+    }
+
+    fn users(&self) -> io::Result<Vec<UserSession>> {
+        answer(&self.snapshot.users)
+    }
+
+    fn hardware_info(&self) -> io::Result<HardwareInfo> {
+        answer(&self.snapshot.hardware_info)
+    }
+
+    fn kernel_limits(&self) -> io::Result<KernelLimits> {
+        answer(&self.snapshot.kernel_limits)
+    }
+
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>> {
+        answer(&self.snapshot.interrupts)
+    }
+
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>> {
+        delayed(&self.interrupt_rates)
+    }
+
+    fn cpu_availability(&self) -> io::Result<CpuAvailability> {
+        answer(&self.cpu_availability)
+    }
+
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        let CpuAvailability { online, affinity, .. } = self.cpu_availability()?;
+        Ok(self.cpu_load()?.map(move |loads| {
+            online
+                .iter()
+                .zip(loads)
+                .filter(|(cpu, _)| affinity.contains(cpu))
+                .map(|(_, load)| load)
+                .collect()
+        }))
+    }
+
+    fn processes(&self) -> io::Result<Vec<Process>> {
+        answer(&self.processes)
+    }
+
+    fn threads(&self, pid: u32) -> io::Result<Vec<Thread>> {
+        process(&self.threads, pid)
+    }
+
+    fn thread_load(&self, pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>> {
+        let thread_load = process(&self.thread_load, pid)?;
+        Ok(DelayedMeasurement::new(Box::new(move || Ok(thread_load.clone()))))
+    }
+
+    fn process_fds(&self, pid: u32) -> io::Result<ProcessFds> {
+        process(&self.process_fds, pid)
+    }
+
+    fn process_io(&self, pid: u32) -> io::Result<ProcessIo> {
+        process(&self.process_io, pid)
+    }
+
+    fn process_memory(&self, pid: u32) -> io::Result<ProcessMemory> {
+        process(&self.process_memory, pid)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use Capability::*;
+        let s = &self.snapshot;
+        let caps = [
+            (CpuLoad, matches!(s.cpu_load, Some(Ok(_)))),
+            (LoadAverage, s.load_average.is_ok()),
+            (Memory, s.memory.is_ok()),
+            (Swap, s.swap.is_ok()),
+            (Uptime, s.uptime.is_ok()),
+            (BootTime, s.boot_time.is_ok()),
+            (BatteryLife, s.battery_life.is_ok()),
+            (OnAcPower, s.on_ac_power.is_ok()),
+            (Mounts, s.mounts.is_ok()),
+            (BlockDeviceStatistics, s.block_device_statistics.is_ok()),
+            (DiskIoRates, self.disk_io_rates.is_ok()),
+            (Networks, s.networks.is_ok()),
+            (NetworkStats, s.network_stats.values().any(|stats| stats.is_ok())),
+            (Wireless, self.wireless.is_ok()),
+            (CpuTemp, s.cpu_temp.is_ok()),
+            (SocketStats, s.socket_stats.is_ok()),
+            (OsInfo, s.os_info.is_ok()),
+            (Users, s.users.is_ok()),
+            (HardwareInfo, s.hardware_info.is_ok()),
+            (KernelLimits, s.kernel_limits.is_ok()),
+            (Interrupts, s.interrupts.is_ok()),
+            (CpuAvailability, self.cpu_availability.is_ok()),
+            (Processes, self.processes.is_ok()),
+            (Threads, !self.threads.is_empty()),
+            (ProcessFds, !self.process_fds.is_empty()),
+            (ProcessIo, !self.process_io.is_empty()),
+            (ProcessMemory, !self.process_memory.is_empty()),
+        ];
+        Capabilities::new(&caps.iter().filter(|(_, ok)| *ok).map(|(cap, _)| *cap).collect::<Vec<_>>())
+    }
+
+    /// Returns the stored snapshot right away, with the current time as the timestamp.
+    fn snapshot(&self, options: &SnapshotOptions) -> Snapshot {
+        let mut snapshot = self.snapshot.clone();
+        snapshot.timestamp = OffsetDateTime::now_utc();
+        if options.cpu_load_duration.is_none() {
+            snapshot.cpu_load = None;
+        }
+        if !options.network_stats {
+            snapshot.network_stats = BTreeMap::new();
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path;
+
+    fn mount(on: &str) -> Filesystem {
+        Filesystem {
+            files: 1,
+            files_total: 10,
+            files_avail: 9,
+            free: ByteSize::mib(1),
+            avail: ByteSize::mib(1),
+            total: ByteSize::mib(2),
+            name_max: 255,
+            fs_type: "ext4".to_string(),
+            fs_mounted_from: "/dev/sda1".to_string(),
+            fs_mounted_on: on.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_fake_platform() {
+        let sys: Box<dyn Platform> = Box::new(FakePlatform::new()
+            .with_uptime(Duration::from_secs(60))
+            .with_mounts(vec![mount("/"), mount("/home")])
+            .with_network(
+                Network { name: "eth0".to_string(), addrs: Vec::new() },
+                NetworkStats { rx_bytes: ByteSize::b(1), tx_bytes: ByteSize::b(2), rx_packets: 3, tx_packets: 4, rx_errors: 0, tx_errors: 0 },
+            ));
+        assert_eq!(sys.uptime().unwrap(), Duration::from_secs(60));
+        assert_eq!(sys.boot_time().unwrap(), OffsetDateTime::UNIX_EPOCH - Duration::from_secs(60));
+        assert_eq!(sys.mount_at_path(path::Path::new("/home")).unwrap().fs_mounted_on, "/home");
+        assert!(matches!(Error::from(sys.mount_at_path(path::Path::new("/usr")).unwrap_err()), Error::NotPresent(_)));
+        assert_eq!(sys.network_stats("eth0").unwrap().tx_packets, 4);
+        assert!(matches!(Error::from(sys.network_stats("eth1").unwrap_err()), Error::NotPresent(_)));
+        assert!(matches!(Error::from(sys.memory().unwrap_err()), Error::Unsupported));
+        assert!(sys.capabilities().supports(Capability::Mounts));
+        assert!(sys.capabilities().supports(Capability::NetworkStats));
+        assert!(!sys.capabilities().supports(Capability::CpuLoad));
+
+        let sys = FakePlatform::new().with_process_fds(42, ProcessFds { files: 3, ..ProcessFds::default() });
+        assert_eq!(sys.process_fds(42).unwrap().total(), 3);
+        assert!(matches!(Error::from(sys.process_fds(43).unwrap_err()), Error::NotPresent(_)));
+        assert!(sys.capabilities().supports(Capability::ProcessFds));
+    }
+
+    #[test]
+    fn test_cpu_load_affine() {
+        let load = |user| CPULoad { user, nice: 0.0, system: 0.0, interrupt: 0.0, idle: 1.0 - user, platform: PlatformCpuLoad::zero() };
+        let sys = FakePlatform::new()
+            .with_cpu_load(vec![load(0.1), load(0.2), load(0.3), load(0.4)])
+            .with_cpu_availability(CpuAvailability { online: vec![0, 2, 3, 5], affinity: vec![2, 5], quota: Some(1.5) });
+        let loads = sys.cpu_load_affine().unwrap().done().unwrap();
+        assert_eq!(loads.iter().map(|l| l.user).collect::<Vec<_>>(), vec![0.2, 0.4]);
+        assert_eq!(sys.cpu_availability().unwrap().parallelism(), 2);
+        assert!(sys.capabilities().supports(Capability::CpuAvailability));
+    }
+
+    #[test]
+    fn test_from_snapshot() {
+        let real = crate::platform::PlatformImpl::new();
+        let snapshot = real.snapshot(&SnapshotOptions { cpu_load_duration: None, network_stats: true });
+        let fake = FakePlatform::from_snapshot(snapshot.clone()).with_cpu_load(Vec::new());
+        assert_eq!(fake.load_average().is_ok(), snapshot.load_average.is_ok());
+        assert_eq!(fake.mounts().map(|m| m.len()).ok(), snapshot.mounts.as_ref().map(|m| m.len()).ok());
+        if let Err(err) = &snapshot.battery_life {
+            assert_eq!(&SnapshotError::from(fake.battery_life().unwrap_err()), err);
+        }
+        assert!(fake.cpu_load().unwrap().done().unwrap().is_empty());
+    }
+}
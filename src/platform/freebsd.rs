@@ -4,10 +4,12 @@ use std::{io, path, ptr, mem, ffi, slice, time};
 use std::os::unix::ffi::OsStrExt;
 use libc::{c_void, c_int, size_t, sysctl, sysctlnametomib, timeval, statfs};
 use crate::data::*;
+use crate::error::Error;
 use super::common::*;
 use super::unix;
 use super::bsd;
 
+#[derive(Default)]
 pub struct PlatformImpl;
 
 macro_rules! sysctl_mib {
@@ -29,7 +31,7 @@ macro_rules! sysctl {
             let mut size = $size;
             if unsafe { sysctl(&mib[0], mib.len() as u32,
                                $dataptr as *mut _ as *mut c_void, &mut size, ptr::null(), 0) } != 0 && $shouldcheck {
-                return Err(io::Error::new(io::ErrorKind::Other, "sysctl() failed"))
+                return Err(io::Error::last_os_error())
             }
             size
         }
@@ -105,7 +107,7 @@ impl Platform for PlatformImpl {
     }
 
     fn swap(&self) -> io::Result<Swap> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn boot_time(&self) -> io::Result<OffsetDateTime> {
@@ -133,23 +135,27 @@ impl Platform for PlatformImpl {
         let mut mptr: *mut statfs = ptr::null_mut();
         let len = unsafe { getmntinfo(&mut mptr, 1_i32) };
         if len < 1 {
-            return Err(io::Error::new(io::ErrorKind::Other, "getmntinfo() failed"))
+            return Err(io::Error::last_os_error())
         }
         let mounts = unsafe { slice::from_raw_parts(mptr, len as usize) };
         Ok(mounts.iter().map(|m| statfs_to_fs(&m)).collect::<Vec<_>>())
     }
 
-    fn mount_at<P: AsRef<path::Path>>(&self, path: P) -> io::Result<Filesystem> {
-        let path = ffi::CString::new(path.as_ref().as_os_str().as_bytes())?;
+    fn mount_at_path(&self, path: &path::Path) -> io::Result<Filesystem> {
+        let path = ffi::CString::new(path.as_os_str().as_bytes())?;
         let mut sfs: statfs = unsafe { mem::zeroed() };
         if unsafe { statfs(path.as_ptr() as *const _, &mut sfs) } != 0 {
-            return Err(io::Error::new(io::ErrorKind::Other, "statfs() failed"));
+            return Err(io::Error::last_os_error());
         }
         Ok(statfs_to_fs(&sfs))
     }
 
     fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>> {
+        Err(Error::Unsupported.into())
     }
 
     fn networks(&self) -> io::Result<BTreeMap<String, Network>> {
@@ -157,7 +163,11 @@ impl Platform for PlatformImpl {
     }
 
     fn network_stats(&self, _interface: &str) -> io::Result<NetworkStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>> {
+        Err(Error::Unsupported.into())
     }
 
     fn cpu_temp(&self) -> io::Result<f32> {
@@ -168,7 +178,68 @@ impl Platform for PlatformImpl {
     }
 
     fn socket_stats(&self) -> io::Result<SocketStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn os_info(&self) -> io::Result<OsInfo> {
+        unix::os_info()
+    }
+
+    fn users(&self) -> io::Result<Vec<UserSession>> {
+        unix::users()
+    }
+
+    fn hardware_info(&self) -> io::Result<HardwareInfo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn kernel_limits(&self) -> io::Result<KernelLimits> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_availability(&self) -> io::Result<CpuAvailability> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn processes(&self) -> io::Result<Vec<Process>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn threads(&self, _pid: u32) -> io::Result<Vec<Thread>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn thread_load(&self, _pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_fds(&self, _pid: u32) -> io::Result<ProcessFds> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_io(&self, _pid: u32) -> io::Result<ProcessIo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_memory(&self, _pid: u32) -> io::Result<ProcessMemory> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use Capability::*;
+        Capabilities::new(&[CpuLoad, LoadAverage, Memory, Uptime, BootTime, BatteryLife, OnAcPower, Mounts, Networks, CpuTemp, OsInfo, Users])
     }
 }
 
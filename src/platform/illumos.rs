@@ -1,8 +1,10 @@
 use super::common::*;
 use super::unix;
 use crate::data::*;
+use crate::error::Error;
 use std::{io, path};
 
+#[derive(Default)]
 pub struct PlatformImpl;
 
 /// An implementation of `Platform` for illumos.
@@ -14,7 +16,7 @@ impl Platform for PlatformImpl {
     }
 
     fn cpu_load(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn load_average(&self) -> io::Result<LoadAverage> {
@@ -22,35 +24,39 @@ impl Platform for PlatformImpl {
     }
 
     fn memory(&self) -> io::Result<Memory> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn swap(&self) -> io::Result<Swap> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn boot_time(&self) -> io::Result<OffsetDateTime> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn battery_life(&self) -> io::Result<BatteryLife> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn on_ac_power(&self) -> io::Result<bool> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn mounts(&self) -> io::Result<Vec<Filesystem>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
-    fn mount_at<P: AsRef<path::Path>>(&self, _: P) -> io::Result<Filesystem> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+    fn mount_at_path(&self, _path: &path::Path) -> io::Result<Filesystem> {
+        Err(Error::Unsupported.into())
     }
 
     fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>> {
+        Err(Error::Unsupported.into())
     }
 
     fn networks(&self) -> io::Result<BTreeMap<String, Network>> {
@@ -58,14 +64,79 @@ impl Platform for PlatformImpl {
     }
 
     fn network_stats(&self, interface: &str) -> io::Result<NetworkStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>> {
+        Err(Error::Unsupported.into())
     }
 
     fn cpu_temp(&self) -> io::Result<f32> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn socket_stats(&self) -> io::Result<SocketStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn os_info(&self) -> io::Result<OsInfo> {
+        unix::os_info()
+    }
+
+    fn users(&self) -> io::Result<Vec<UserSession>> {
+        unix::users()
+    }
+
+    fn hardware_info(&self) -> io::Result<HardwareInfo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn kernel_limits(&self) -> io::Result<KernelLimits> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_availability(&self) -> io::Result<CpuAvailability> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn processes(&self) -> io::Result<Vec<Process>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn threads(&self, _pid: u32) -> io::Result<Vec<Thread>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn thread_load(&self, _pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_fds(&self, _pid: u32) -> io::Result<ProcessFds> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_io(&self, _pid: u32) -> io::Result<ProcessIo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_memory(&self, _pid: u32) -> io::Result<ProcessMemory> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use Capability::*;
+        Capabilities::new(&[LoadAverage, Networks, OsInfo, Users])
     }
 }
@@ -1,45 +1,199 @@
 use super::common::*;
 use super::unix;
 use crate::data::*;
+use crate::error::Error;
 use libc::statvfs;
 use libc::{c_char, c_long, c_schar, c_uint, c_ulong, c_ushort};
+use nom::branch::alt;
 use nom::bytes::complete::{tag, take_till, take_until};
 use nom::character::complete::{digit1, multispace0, not_line_ending, space1};
 use nom::character::is_space;
-use nom::combinator::{complete, map, map_res, opt, verify};
+use nom::combinator::{all_consuming, complete, map, map_res, opt, verify};
 use nom::error::ParseError;
-use nom::multi::{fold_many0, many0, many1};
-use nom::sequence::{delimited, preceded, tuple};
+use nom::multi::{fold_many0, many0, many1, many_m_n, separated_list0};
+use nom::number::complete::{double, float};
+use nom::sequence::{delimited, preceded, terminated, tuple};
 use nom::{IResult, Parser};
-use std::io::Read;
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::str;
 use std::time::Duration;
 use std::{fs, io, mem, path};
 
-fn read_file(path: &str) -> io::Result<String> {
-    let mut s = String::new();
-    fs::File::open(path)
-        .and_then(|mut f| f.read_to_string(&mut s))
-        .map(|_| s)
-}
-
-fn value_from_file<T: str::FromStr>(path: &str) -> io::Result<T> {
-    read_file(path)?
-        .trim_end_matches('\n')
-        .parse()
-        .map_err(|_| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("File: \"{}\" doesn't contain an int value", &path),
-            )
-        })
+/// Directory of a capture holding the results of system calls, which aren't files
+const RECORDS_DIR: &str = "systemstat";
+
+/// Where the files under `/proc`, `/sys` etc. are read from
+#[derive(Debug, Clone)]
+enum Files {
+    /// The running system
+    Live,
+    /// The running system, copying everything that's read into a directory
+    Capture(PathBuf),
+    /// A directory previously filled by `Capture`
+    Replay(PathBuf),
+}
+
+/// Parse the contents of a file with a nom parser
+fn parse_data<T>(path: &str, data: &str, parser: impl Fn(&str) -> IResult<&str, T>) -> io::Result<T> {
+    parser(data).map(|(_, res)| res).map_err(|err| {
+        let (line, message) = match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => (
+                Some(data[..data.len() - e.input.len()].matches('\n').count() + 1),
+                e.code.description().to_string(),
+            ),
+            nom::Err::Incomplete(_) => (None, "Unexpected end of file".to_string()),
+        };
+        Error::Parse {
+            path: path.to_string(),
+            line,
+            message,
+        }
+        .into()
+    })
+}
+
+// Write a captured file, creating its parent directories
+fn save(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, data)
+}
+
+impl Files {
+    // Where a file of the running system goes in a capture directory
+    fn in_dir(dir: &Path, path: &str) -> PathBuf {
+        dir.join(path.trim_start_matches('/'))
+    }
+
+    // Where a file is actually read from
+    fn path(&self, path: &str) -> PathBuf {
+        match self {
+            Files::Replay(dir) => Files::in_dir(dir, path),
+            _ => PathBuf::from(path),
+        }
+    }
+
+    fn is_replay(&self) -> bool {
+        matches!(self, Files::Replay(_))
+    }
+
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let data = fs::read(self.path(path))?;
+        if let Files::Capture(dir) = self {
+            save(&Files::in_dir(dir, path), &data)?;
+        }
+        Ok(data)
+    }
+
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn value_from_file<T: str::FromStr>(&self, path: &str) -> io::Result<T> {
+        self.read_file(path)?
+            .trim_end_matches('\n')
+            .parse()
+            .map_err(|_| {
+                Error::Parse {
+                    path: path.to_string(),
+                    line: Some(1),
+                    message: "Unexpected value".to_string(),
+                }
+                .into()
+            })
+    }
+
+    /// Read a file and parse it with a nom parser
+    fn parse_file<T>(&self, path: &str, parser: impl Fn(&str) -> IResult<&str, T>) -> io::Result<T> {
+        parse_data(path, &self.read_file(path)?, parser)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        let found = self.path(path);
+        if let Files::Capture(dir) = self {
+            // Only the existence matters, so unreadable files are captured empty
+            let captured = Files::in_dir(dir, path);
+            let _ = if found.is_dir() {
+                fs::create_dir_all(captured)
+            } else if found.exists() {
+                save(&captured, &fs::read(&found).unwrap_or_default())
+            } else {
+                Ok(())
+            };
+        }
+        found.exists()
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        self.exists(path) && self.path(path).is_dir()
+    }
+
+    /// List the names of the entries of a directory, sorted
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(self.path(path))? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Files::Capture(dir) = self {
+                // Entries are captured empty, unless their contents are read later
+                let captured = Files::in_dir(dir, path).join(&name);
+                if entry.path().is_dir() {
+                    fs::create_dir_all(captured)?;
+                } else if !captured.exists() {
+                    save(&captured, b"")?;
+                }
+            }
+            names.push(name);
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Read the target of a symbolic link
+    fn read_link(&self, path: &str) -> io::Result<PathBuf> {
+        let target = fs::read_link(self.path(path))?;
+        if let Files::Capture(dir) = self {
+            let captured = Files::in_dir(dir, path);
+            // Replace what `read_dir` may have created in its place
+            let _ = fs::remove_file(&captured).or_else(|_| fs::remove_dir(&captured));
+            if let Some(parent) = captured.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            std::os::unix::fs::symlink(&target, &captured)?;
+        }
+        Ok(target)
+    }
+
+    /// Get something that's not read from a file (i.e. the result of a system call)
+    /// as the text produced by `live`, which is what gets captured and replayed,
+    /// and parse it with a nom parser
+    fn parse_record<T>(
+        &self,
+        name: &str,
+        live: impl FnOnce() -> io::Result<String>,
+        parser: impl Fn(&str) -> IResult<&str, T>,
+    ) -> io::Result<T> {
+        let data = match self {
+            Files::Live => live()?,
+            Files::Capture(dir) => {
+                let data = live()?;
+                save(&dir.join(RECORDS_DIR).join(name), data.as_bytes())?;
+                data
+            }
+            Files::Replay(dir) => fs::read_to_string(dir.join(RECORDS_DIR).join(name))?,
+        };
+        parse_data(name, &data, parser)
+    }
 }
 
 fn capacity(charge_full: i32, charge_now: i32) -> f32 {
     charge_now as f32 / charge_full as f32
 }
 
+#[allow(clippy::cast_abs_to_unsigned)]
 fn time(on_ac: bool, charge_full: i32, charge_now: i32, current_now: i32) -> Duration {
     if current_now != 0 {
         if on_ac {
@@ -58,6 +212,7 @@ fn time(on_ac: bool, charge_full: i32, charge_now: i32, current_now: i32) -> Dur
 
 /// A combinator that takes a parser `inner` and produces a parser that also consumes both leading and
 /// trailing whitespace, returning the output of `inner`.
+#[allow(clippy::multiple_bound_locations)]
 fn ws<'a, F: 'a, O, E: ParseError<&'a str>>(
     inner: F,
 ) -> impl FnMut(&'a str) -> IResult<&'a str, O, E>
@@ -75,26 +230,26 @@ fn usize_s(input: &str) -> IResult<&str, usize> {
     )(input)
 }
 
-// Parse `cpuX`, where X is a number
-fn proc_stat_cpu_prefix(input: &str) -> IResult<&str, ()> {
-    map(tuple((tag("cpu"), digit1)), |_| ())(input)
+// Parse `cpuX`, where X is a number, into X
+fn proc_stat_cpu_prefix(input: &str) -> IResult<&str, usize> {
+    preceded(tag("cpu"), map_res(digit1, str::FromStr::from_str))(input)
 }
 
-// Parse a `/proc/stat` CPU line into a `CpuTime` struct
-fn proc_stat_cpu_time(input: &str) -> IResult<&str, CpuTime> {
+// Parse a `/proc/stat` CPU line into the CPU ID and a `CpuTime` struct
+fn proc_stat_cpu_time(input: &str) -> IResult<&str, (usize, CpuTime)> {
     map(
-        preceded(
+        tuple((
             ws(proc_stat_cpu_prefix),
             tuple((usize_s, usize_s, usize_s, usize_s, usize_s, usize_s)),
-        ),
-        |(user, nice, system, idle, iowait, irq)| CpuTime {
+        )),
+        |(cpu, (user, nice, system, idle, iowait, irq))| (cpu, CpuTime {
             user,
             nice,
             system,
             idle,
             interrupt: irq,
             other: iowait,
-        },
+        }),
     )(input)
 }
 
@@ -104,7 +259,7 @@ fn proc_stat_cpu_aggregate(input: &str) -> IResult<&str, ()> {
 }
 
 // Parse `/proc/stat` to extract per-CPU loads
-fn proc_stat_cpu_times(input: &str) -> IResult<&str, Vec<CpuTime>> {
+fn proc_stat_cpu_times(input: &str) -> IResult<&str, Vec<(usize, CpuTime)>> {
     preceded(
         map(ws(not_line_ending), proc_stat_cpu_aggregate),
         many1(map_res(ws(not_line_ending), |input| {
@@ -144,20 +299,41 @@ softirq 81473629 1251347 8827732 10 325789 37 0 177903 43807896 2777 27080138
 ";
     let result = proc_stat_cpu_times(input).unwrap().1;
     assert_eq!(result.len(), 16);
-    assert_eq!(result[0].user, 444919);
-    assert_eq!(result[0].nice, 3155);
-    assert_eq!(result[0].system, 198700);
-    assert_eq!(result[0].idle, 24405593);
-    assert_eq!(result[0].other, 4622);
-    assert_eq!(result[0].interrupt, 0);
-}
-
-/// Get the current per-CPU `CpuTime` statistics
-fn cpu_time() -> io::Result<Vec<CpuTime>> {
-    read_file("/proc/stat").and_then(|data| {
-        proc_stat_cpu_times(&data)
-            .map(|(_, res)| res)
-            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+    assert_eq!(result[0].0, 0);
+    assert_eq!(result[0].1.user, 444919);
+    assert_eq!(result[0].1.nice, 3155);
+    assert_eq!(result[0].1.system, 198700);
+    assert_eq!(result[0].1.idle, 24405593);
+    assert_eq!(result[0].1.other, 4622);
+    assert_eq!(result[0].1.interrupt, 0);
+    assert_eq!(result[15].0, 15);
+}
+
+/// Get the current per-CPU `CpuTime` statistics, with the CPU IDs
+fn cpu_time(files: &Files) -> io::Result<Vec<(usize, CpuTime)>> {
+    files.parse_file("/proc/stat", proc_stat_cpu_times)
+}
+
+/// Measure the load of the CPUs whose IDs are accepted by `filter`
+fn measure_cpu_load(
+    files: &Files,
+    filter: impl Fn(usize) -> bool + Send + 'static,
+) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+    let files = files.clone();
+    let sample = move || {
+        cpu_time(&files).map(|times| {
+            times
+                .into_iter()
+                .filter(|(cpu, _)| filter(*cpu))
+                .map(|(_, time)| time)
+                .collect::<Vec<_>>()
+        })
+    };
+    DelayedMeasurement::from_samples(sample, |prev, now, _| {
+        now.iter()
+            .zip(prev.iter())
+            .map(|(now, prev)| (*now - prev).to_cpuload())
+            .collect::<Vec<_>>()
     })
 }
 
@@ -196,6 +372,7 @@ fn proc_meminfo(input: &str) -> IResult<&str, BTreeMap<String, ByteSize>> {
 }
 
 #[test]
+#[allow(clippy::unnecessary_to_owned)]
 fn test_proc_meminfo() {
     let input = "MemTotal:       32345596 kB
 MemFree:        13160208 kB
@@ -262,12 +439,8 @@ DirectMap1G:    13631488 kB
 }
 
 /// Get memory statistics
-fn memory_stats() -> io::Result<BTreeMap<String, ByteSize>> {
-    read_file("/proc/meminfo").and_then(|data| {
-        proc_meminfo(&data)
-            .map(|(_, res)| res)
-            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
-    })
+fn memory_stats(files: &Files) -> io::Result<BTreeMap<String, ByteSize>> {
+    files.parse_file("/proc/meminfo", proc_meminfo)
 }
 
 // Parse a single word
@@ -427,25 +600,42 @@ FRAG6: inuse 0 memory 0
 }
 
 /// Stat a mountpoint to gather filesystem statistics
-fn stat_mount(mount: ProcMountsData) -> io::Result<Filesystem> {
-    let mut info: statvfs = unsafe { mem::zeroed() };
-    let target = format!("{}\0", mount.target);
-    let result = unsafe { statvfs(target.as_ptr() as *const c_char, &mut info) };
-    match result {
-        0 => Ok(Filesystem {
-            files: (info.f_files as usize).saturating_sub(info.f_ffree as usize),
-            files_total: info.f_files as usize,
-            files_avail: info.f_favail as usize,
-            free: ByteSize::b(info.f_bfree as u64 * info.f_bsize as u64),
-            avail: ByteSize::b(info.f_bavail as u64 * info.f_bsize as u64),
-            total: ByteSize::b(info.f_blocks as u64 * info.f_bsize as u64),
-            name_max: info.f_namemax as usize,
-            fs_type: mount.fstype,
-            fs_mounted_from: mount.source,
-            fs_mounted_on: mount.target,
-        }),
-        _ => Err(io::Error::last_os_error()),
-    }
+fn stat_mount(files: &Files, mount: ProcMountsData) -> io::Result<Filesystem> {
+    // Recorded as "files files_total files_avail free avail total name_max", sizes in bytes
+    let name = format!("statvfs/{}", mount.target.replace('%', "%25").replace('/', "%2F"));
+    let (files, files_total, files_avail, free, avail, total, name_max) = files.parse_record(
+        &name,
+        || {
+            let mut info: statvfs = unsafe { mem::zeroed() };
+            let target = format!("{}\0", mount.target);
+            match unsafe { statvfs(target.as_ptr() as *const c_char, &mut info) } {
+                0 => Ok(format!(
+                    "{} {} {} {} {} {} {}\n",
+                    (info.f_files as usize).saturating_sub(info.f_ffree as usize),
+                    info.f_files,
+                    info.f_favail,
+                    info.f_bfree as u64 * info.f_bsize as u64,
+                    info.f_bavail as u64 * info.f_bsize as u64,
+                    info.f_blocks as u64 * info.f_bsize as u64,
+                    info.f_namemax,
+                )),
+                _ => Err(io::Error::last_os_error()),
+            }
+        },
+        |input| tuple((usize_s, usize_s, usize_s, u64_s, u64_s, u64_s, usize_s))(input),
+    )?;
+    Ok(Filesystem {
+        files,
+        files_total,
+        files_avail,
+        free: ByteSize::b(free),
+        avail: ByteSize::b(avail),
+        total: ByteSize::b(total),
+        name_max,
+        fs_type: mount.fstype,
+        fs_mounted_from: mount.source,
+        fs_mounted_on: mount.target,
+    })
 }
 
 // Parse a line of `/proc/diskstats`
@@ -472,6 +662,8 @@ fn proc_diskstats_line(input: &str) -> IResult<&str, BlockDeviceStats> {
             time_in_queue,
         )| BlockDeviceStats {
             name: name.to_string(),
+            kind: BlockDeviceKind::Disk,
+            parent: None,
             read_ios,
             read_merges,
             read_sectors,
@@ -510,72 +702,1490 @@ fn test_proc_diskstats() {
     assert_eq!(result[3].write_ios, 1132993);
 }
 
-pub struct PlatformImpl;
+// Parse `/etc/os-release` into a key-value map, removing quotes around values
+fn os_release(input: &str) -> BTreeMap<String, String> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            (key.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\"))
+        })
+        .collect()
+}
+
+#[test]
+fn test_os_release() {
+    let input = r#"PRETTY_NAME="Debian GNU/Linux 12 (bookworm)"
+NAME="Debian GNU/Linux"
+VERSION_ID="12"
+VERSION="12 (bookworm)"
+VERSION_CODENAME=bookworm
+ID=debian
+# a comment
+HOME_URL='https://www.debian.org/'
+"#;
+    let result = os_release(input);
+    assert_eq!(result.len(), 7);
+    assert_eq!(result["NAME"], "Debian GNU/Linux");
+    assert_eq!(result["VERSION_ID"], "12");
+    assert_eq!(result["ID"], "debian");
+    assert_eq!(result["VERSION_CODENAME"], "bookworm");
+    assert_eq!(result["HOME_URL"], "https://www.debian.org/");
+}
+
+/// Get the distribution information from os-release
+fn distribution(files: &Files) -> io::Result<Distribution> {
+    files.read_file("/etc/os-release")
+        .or_else(|_| files.read_file("/usr/lib/os-release"))
+        .map(|data| {
+            let mut fields = os_release(&data);
+            Distribution {
+                // Defaults as specified by os-release(5)
+                id: fields.remove("ID").unwrap_or_else(|| "linux".to_string()),
+                name: fields.remove("NAME").unwrap_or_else(|| "Linux".to_string()),
+                version: fields.remove("VERSION"),
+                version_id: fields.remove("VERSION_ID"),
+            }
+        })
+}
+
+// Detect the container or hypervisor from the contents of `/proc/1/cgroup` and
+// `/sys/class/dmi/id/product_name`, and whether `/.dockerenv` and `/run/.containerenv` exist
+fn detect_virtualization(
+    dockerenv: bool,
+    containerenv: bool,
+    cgroup: Option<&str>,
+    product_name: Option<&str>,
+) -> Option<Virtualization> {
+    if dockerenv {
+        return Some(Virtualization::Docker);
+    }
+    if containerenv {
+        return Some(Virtualization::Podman);
+    }
+    if let Some(cgroup) = cgroup {
+        if cgroup.contains("kubepods") {
+            return Some(Virtualization::Kubernetes);
+        } else if cgroup.contains("libpod") {
+            return Some(Virtualization::Podman);
+        } else if cgroup.contains("docker") {
+            return Some(Virtualization::Docker);
+        } else if cgroup.contains("/lxc") {
+            return Some(Virtualization::Lxc);
+        }
+    }
+    match product_name.map(str::trim) {
+        Some(p) if p.starts_with("KVM") => Some(Virtualization::Kvm),
+        Some(p) if p.starts_with("QEMU") || p.starts_with("Standard PC") => Some(Virtualization::Qemu),
+        Some(p) if p.starts_with("VMware") => Some(Virtualization::VMware),
+        Some(p) if p.starts_with("VirtualBox") => Some(Virtualization::VirtualBox),
+        Some(p) if p.starts_with("HVM domU") => Some(Virtualization::Xen),
+        Some("Virtual Machine") => Some(Virtualization::HyperV),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_detect_virtualization() {
+    assert_eq!(detect_virtualization(false, false, Some("0::/init.scope\n"), Some("ThinkPad X1\n")), None);
+    assert_eq!(detect_virtualization(true, false, Some("0::/\n"), None), Some(Virtualization::Docker));
+    assert_eq!(
+        detect_virtualization(false, false, Some("12:cpu,cpuacct:/docker/1e2f3a4b5c6d\n"), None),
+        Some(Virtualization::Docker)
+    );
+    assert_eq!(
+        detect_virtualization(false, false, Some("0::/kubepods/besteffort/pod1234/abcd\n"), Some("KVM\n")),
+        Some(Virtualization::Kubernetes)
+    );
+    assert_eq!(
+        detect_virtualization(false, false, Some("0::/init.scope\n"), Some("Standard PC (Q35 + ICH9, 2009)\n")),
+        Some(Virtualization::Qemu)
+    );
+    assert_eq!(
+        detect_virtualization(false, false, None, Some("VMware Virtual Platform\n")),
+        Some(Virtualization::VMware)
+    );
+}
+
+/// Detect the virtualization or container technology we're running under
+fn virtualization(files: &Files) -> Option<Virtualization> {
+    detect_virtualization(
+        files.exists("/.dockerenv"),
+        files.exists("/run/.containerenv"),
+        files.read_file("/proc/1/cgroup").ok().as_deref(),
+        files.read_file("/sys/class/dmi/id/product_name").ok().as_deref(),
+    )
+}
+
+// Parse the contents of a utmp database, which is an array of the C library's `struct utmpx`
+fn utmp(data: &[u8]) -> Vec<UserSession> {
+    data.chunks_exact(mem::size_of::<unix::utmpx>())
+        .filter_map(|record| {
+            // Any bytes make a valid record, it's all integers
+            let ut = unsafe { std::ptr::read_unaligned(record.as_ptr() as *const unix::utmpx) };
+            unix::utmpx_session(&ut)
+        })
+        .collect()
+}
+
+#[test]
+fn test_utmp() {
+    fn record(ut_type: libc::c_short, pid: i32, line: &str, user: &str, host: &str, sec: i32) -> Vec<u8> {
+        fn copy(field: &mut [libc::c_char], value: &str) {
+            for (c, b) in field.iter_mut().zip(value.bytes()) {
+                *c = b as libc::c_char;
+            }
+        }
+        let mut ut: unix::utmpx = unsafe { mem::zeroed() };
+        ut.ut_type = ut_type;
+        ut.ut_pid = pid;
+        copy(&mut ut.ut_line, line);
+        copy(&mut ut.ut_user, user);
+        copy(&mut ut.ut_host, host);
+        ut.ut_tv.tv_sec = sec as _;
+        ut.ut_tv.tv_usec = 500000;
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&ut as *const unix::utmpx as *const u8, mem::size_of::<unix::utmpx>())
+        };
+        bytes.to_vec()
+    }
+    let mut input = Vec::new();
+    input.extend(record(2, 0, "~", "reboot", "6.1.0", 1700000000));
+    input.extend(record(6, 712, "tty1", "LOGIN", "", 1700000010));
+    input.extend(record(7, 1234, "pts/0", "alice", "192.0.2.10", 1700000100));
+    input.extend(record(7, 1300, "tty2", "bob", "", 1700000200));
+    input.extend(record(8, 1400, "pts/1", "", "", 1700000300));
+    input.extend(record(7, -1, "pts/2", "mallory", "", 1700000400));
+    input.extend(record(7, 1500, "pts/3", "eve", "", -1));
+    let path = std::env::temp_dir().join(format!("systemstat-test-utmp-{}", std::process::id()));
+    fs::write(&path, &input).unwrap();
+    let result = PlatformImpl::default().users_from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].username, "alice");
+    assert_eq!(result[0].tty, "pts/0");
+    assert_eq!(result[0].host, "192.0.2.10");
+    assert_eq!(result[0].pid, 1234);
+    assert_eq!(
+        result[0].login_time,
+        OffsetDateTime::from_unix_timestamp(1700000100).unwrap() + Duration::from_millis(500)
+    );
+    assert_eq!(result[1].username, "bob");
+    assert_eq!(result[1].host, "");
+}
+
+// Read the DMI/SMBIOS information exposed by the kernel in the given directory
+fn dmi_hardware_info(files: &Files, dir: &str) -> io::Result<HardwareInfo> {
+    if !files.is_dir(dir) {
+        return Err(Error::NotPresent("DMI information".to_string()).into());
+    }
+    // Unreadable (root-only), missing and empty fields are all `None`
+    let field = |name: &str| {
+        files.read_file(&format!("{}/{}", dir, name))
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    Ok(HardwareInfo {
+        system_vendor: field("sys_vendor"),
+        product_name: field("product_name"),
+        serial: field("product_serial"),
+        uuid: field("product_uuid"),
+        board_vendor: field("board_vendor"),
+        board_name: field("board_name"),
+        bios_vendor: field("bios_vendor"),
+        bios_version: field("bios_version"),
+        bios_date: field("bios_date"),
+        chassis_type: field("chassis_type")
+            .and_then(|v| v.parse().ok())
+            .map(ChassisType::from_smbios),
+    })
+}
+
+#[test]
+fn test_dmi_hardware_info() {
+    let dir = std::env::temp_dir().join(format!("systemstat-test-dmi-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("sys_vendor"), "LENOVO\n").unwrap();
+    fs::write(dir.join("product_name"), "20XW0055GE\n").unwrap();
+    fs::write(dir.join("board_name"), "\n").unwrap();
+    fs::write(dir.join("bios_date"), "07/21/2023\n").unwrap();
+    fs::write(dir.join("chassis_type"), "10\n").unwrap();
+    let result = dmi_hardware_info(&Files::Live, &dir.to_string_lossy()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(result.system_vendor.as_deref(), Some("LENOVO"));
+    assert_eq!(result.product_name.as_deref(), Some("20XW0055GE"));
+    assert_eq!(result.serial, None);
+    assert_eq!(result.board_name, None);
+    assert_eq!(result.bios_date.as_deref(), Some("07/21/2023"));
+    assert_eq!(result.chassis_type, Some(ChassisType::Notebook));
+    assert!(dmi_hardware_info(&Files::Live, &dir.to_string_lossy()).is_err());
+}
+
+// Parse `/proc/sys/fs/file-nr` into (allocated, max)
+fn proc_file_nr(input: &str) -> IResult<&str, (u64, u64)> {
+    map(tuple((u64_s, u64_s, u64_s)), |(allocated, _unused, max)| {
+        (allocated, max)
+    })(input)
+}
+
+// Parse `/proc/sys/fs/inode-nr` into (allocated, free)
+fn proc_inode_nr(input: &str) -> IResult<&str, (u64, u64)> {
+    tuple((u64_s, u64_s))(input)
+}
+
+// Parse `/proc/loadavg` to get the number of existing scheduling entities
+fn proc_loadavg_tasks(input: &str) -> IResult<&str, u64> {
+    preceded(
+        tuple((ws(word_s), ws(word_s), ws(word_s), u64_s, tag("/"))),
+        u64_s,
+    )(input)
+}
+
+#[test]
+fn test_kernel_limits() {
+    assert_eq!(proc_file_nr("9824\t0\t9223372036854775807\n").unwrap().1, (9824, 9223372036854775807));
+    assert_eq!(proc_inode_nr("210436\t52014\n").unwrap().1, (210436, 52014));
+    assert_eq!(proc_loadavg_tasks("0.52 0.58 0.59 3/1246 286173\n").unwrap().1, 1246);
+}
+
+// Parse `/proc/loadavg` to get the load averages
+fn proc_loadavg(input: &str) -> IResult<&str, LoadAverage> {
+    map(tuple((ws(float), ws(float), ws(float))), |(one, five, fifteen)| LoadAverage {
+        one,
+        five,
+        fifteen,
+    })(input)
+}
+
+// Parse `/proc/uptime` to get the time since boot
+fn proc_uptime(input: &str) -> IResult<&str, Duration> {
+    map(ws(double), |secs| Duration::from_secs(secs as u64))(input)
+}
+
+#[test]
+fn test_proc_loadavg() {
+    let load = proc_loadavg("0.52 0.58 0.59 3/1246 286173\n").unwrap().1;
+    assert_eq!((load.one, load.five, load.fifteen), (0.52, 0.58, 0.59));
+    assert_eq!(proc_uptime("350735.47 234388.90\n").unwrap().1, Duration::from_secs(350735));
+}
+
+#[test]
+fn test_parse_file_error() {
+    let path = std::env::temp_dir().join(format!("systemstat-test-file-nr-{}", std::process::id()));
+    fs::write(&path, "\n1024 0 x\n").unwrap();
+    let err = Files::Live.parse_file(&path.to_string_lossy(), proc_file_nr).unwrap_err();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    match Error::from(err) {
+        Error::Parse { line, .. } => assert_eq!(line, Some(2)),
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+/// Parse an unsigned 64-bit integer out of a string, surrounded by whitespace
+fn u64_s(input: &str) -> IResult<&str, u64> {
+    map_res(ws(digit1), str::FromStr::from_str)(input)
+}
+
+// Parse the `CPU0 CPU1 ...` header of `/proc/interrupts` and `/proc/softirqs` into the number of CPUs
+fn proc_interrupts_header(input: &str) -> IResult<&str, usize> {
+    map(many1(ws(tuple((tag("CPU"), digit1)))), |cpus| cpus.len())(input)
+}
+
+// Parse a line of `/proc/interrupts` or `/proc/softirqs` into an `Interrupt` struct
+fn proc_interrupts_line(input: &str, cpus: usize, kind: InterruptKind) -> IResult<&str, Interrupt> {
+    map(
+        tuple((ws(take_until(":")), tag(":"), many_m_n(0, cpus, u64_s), not_line_ending)),
+        |(irq, _, per_cpu, description): (&str, _, _, &str)| Interrupt {
+            kind,
+            irq: irq.to_string(),
+            per_cpu,
+            description: description.split_whitespace().collect::<Vec<_>>().join(" "),
+        },
+    )(input)
+}
+
+// Parse `/proc/interrupts` or `/proc/softirqs` to get a Vec<Interrupt>
+fn proc_interrupts(input: &str, kind: InterruptKind) -> IResult<&str, Vec<Interrupt>> {
+    let (input, cpus) = proc_interrupts_header(input)?;
+    // A line that doesn't parse stops `many0`, so fail on it instead of dropping everything after it
+    all_consuming(many0(ws(map_res(not_line_ending, move |input| {
+        proc_interrupts_line(input, cpus, kind)
+            .map(|(_, res)| res)
+            .map_err(|_| ())
+    }))))(input)
+}
+
+#[test]
+fn test_proc_interrupts() {
+    let input = "            CPU0       CPU1       CPU2       CPU3       
+   0:         36          0          0          0   IO-APIC   2-edge      timer
+   8:          0          0          1          0   IO-APIC   8-edge      rtc0
+ 127:     187211          0      52034          0  PCI-MSI 327680-edge      xhci_hcd
+ NMI:         81         77         79         80   Non-maskable interrupts
+ LOC:   11823390    9927342   12280043    9703922   Local timer interrupts
+ ERR:          0
+ MIS:          0
+";
+    let result = proc_interrupts(input, InterruptKind::Hardware).unwrap().1;
+    assert_eq!(result.len(), 7);
+    assert_eq!(result[2].irq, "127");
+    assert_eq!(result[2].per_cpu, vec![187211, 0, 52034, 0]);
+    assert_eq!(result[2].description, "PCI-MSI 327680-edge xhci_hcd");
+    assert_eq!(result[4].irq, "LOC");
+    assert_eq!(result[4].description, "Local timer interrupts");
+    assert_eq!(result[5].irq, "ERR");
+    assert_eq!(result[5].per_cpu, vec![0]);
+
+    let input = "                    CPU0       CPU1
+          HI:          1          0
+       TIMER:     941394     722386
+      NET_RX:      25497      36154
+         RCU:    1073413     938478
+";
+    let result = proc_interrupts(input, InterruptKind::Soft).unwrap().1;
+    assert_eq!(result.len(), 4);
+    assert_eq!(result[2].irq, "NET_RX");
+    assert_eq!(result[2].per_cpu, vec![25497, 36154]);
+    assert_eq!(result[2].description, "");
+    assert_eq!(result[2].kind, InterruptKind::Soft);
+
+    let input = "            CPU0       CPU1
+   0:         36          0   IO-APIC   2-edge      timer
+ garbage
+ NMI:         81         77   Non-maskable interrupts
+";
+    let err = parse_data("/proc/interrupts", input, |input| proc_interrupts(input, InterruptKind::Hardware)).unwrap_err();
+    match Error::from(err) {
+        Error::Parse { line, .. } => assert_eq!(line, Some(3)),
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+/// Get the current hardware interrupt and softirq counters
+fn interrupts(files: &Files) -> io::Result<Vec<Interrupt>> {
+    let mut result = Vec::new();
+    for (path, kind) in [
+        ("/proc/interrupts", InterruptKind::Hardware),
+        ("/proc/softirqs", InterruptKind::Soft),
+    ] {
+        result.extend(files.parse_file(path, |data| proc_interrupts(data, kind))?);
+    }
+    Ok(result)
+}
+
+// Detect which cgroup hierarchy is mounted at the given path
+fn cgroup_version(files: &Files, root: &str) -> Option<CgroupVersion> {
+    if files.exists(&format!("{}/cgroup.controllers", root)) {
+        Some(CgroupVersion::V2)
+    } else if files.exists(&format!("{}/unified/cgroup.controllers", root)) {
+        Some(CgroupVersion::Hybrid)
+    } else if files.read_dir(root).map(|entries| !entries.is_empty()).unwrap_or(false) {
+        Some(CgroupVersion::V1)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_cgroup_version() {
+    let dir = std::env::temp_dir().join(format!("systemstat-test-cgroup-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    assert_eq!(cgroup_version(&Files::Live, &dir.to_string_lossy()), None);
+    fs::create_dir_all(dir.join("memory")).unwrap();
+    assert_eq!(cgroup_version(&Files::Live, &dir.to_string_lossy()), Some(CgroupVersion::V1));
+    fs::create_dir_all(dir.join("unified")).unwrap();
+    fs::write(dir.join("unified").join("cgroup.controllers"), "memory pids\n").unwrap();
+    assert_eq!(cgroup_version(&Files::Live, &dir.to_string_lossy()), Some(CgroupVersion::Hybrid));
+    fs::write(dir.join("cgroup.controllers"), "cpuset cpu io memory pids\n").unwrap();
+    assert_eq!(cgroup_version(&Files::Live, &dir.to_string_lossy()), Some(CgroupVersion::V2));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Check whether there's a power supply of the given type (e.g. `Battery` or `Mains`)
+fn has_power_supply(files: &Files, kind: &str) -> bool {
+    files.read_dir("/sys/class/power_supply")
+        .map(|entries| {
+            entries.iter().any(|name| {
+                files.value_from_file::<String>(&format!("/sys/class/power_supply/{}/type", name))
+                    .map(|t| t == kind)
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+// Parse a CPU list like `0-3,8,10-11`, as in `/sys/devices/system/cpu/online`, into CPU IDs
+fn cpu_list(input: &str) -> IResult<&str, Vec<usize>> {
+    map(
+        ws(separated_list0(tag(","), tuple((usize_s, opt(preceded(tag("-"), usize_s)))))),
+        |ranges| {
+            ranges
+                .into_iter()
+                .flat_map(|(first, last)| first..=last.unwrap_or(first))
+                .collect()
+        },
+    )(input)
+}
+
+// Get the affinity mask of the current process, as a CPU list
+fn sched_affinity() -> io::Result<String> {
+    let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let cpus = (0..8 * mem::size_of::<libc::cpu_set_t>())
+        .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+        .map(|cpu| cpu.to_string())
+        .collect::<Vec<_>>();
+    Ok(cpus.join(",") + "\n")
+}
+
+#[test]
+fn test_cpu_list() {
+    assert_eq!(cpu_list("0-3,8,10-11\n").unwrap().1, vec![0, 1, 2, 3, 8, 10, 11]);
+    assert_eq!(cpu_list("0\n").unwrap().1, vec![0]);
+    assert_eq!(cpu_list("\n").unwrap().1, Vec::<usize>::new());
+    let affinity = cpu_list(&sched_affinity().unwrap()).unwrap().1;
+    assert!(!affinity.is_empty());
+}
+
+// Parse cgroup v2 `cpu.max` (`$MAX $PERIOD`, where `$MAX` can be `max`) into CPUs
+fn cgroup_cpu_max(input: &str) -> IResult<&str, Option<f64>> {
+    map(
+        tuple((ws(alt((map(tag("max"), |_| None), map(u64_s, Some)))), u64_s)),
+        |(max, period)| max.filter(|_| period > 0).map(|max| max as f64 / period as f64),
+    )(input)
+}
+
+// Find the path of the cgroup of a process, from `/proc/<pid>/cgroup`, in the unified hierarchy
+// or (if `controller` is given) in the legacy hierarchy with that controller
+fn proc_cgroup_path<'a>(data: &'a str, controller: Option<&str>) -> Option<&'a str> {
+    data.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ':');
+            Some((fields.next()?, fields.next()?, fields.next()?))
+        })
+        .find(|(_, controllers, _)| match controller {
+            Some(controller) => controllers.split(',').any(|c| c == controller),
+            None => controllers.is_empty(),
+        })
+        .map(|(_, _, path)| path)
+}
+
+/// Get the lowest CPU quota of the cgroup of the current process and its ancestors, in CPUs
+fn cgroup_cpu_quota(files: &Files) -> Option<f64> {
+    let root = "/sys/fs/cgroup";
+    let cgroups = files.read_file("/proc/self/cgroup").ok()?;
+    let (mount, path) = match cgroup_version(files, root)? {
+        CgroupVersion::V2 => (root.to_string(), proc_cgroup_path(&cgroups, None)?),
+        CgroupVersion::V1 | CgroupVersion::Hybrid => (
+            ["cpu,cpuacct", "cpu"]
+                .iter()
+                .map(|dir| format!("{}/{}", root, dir))
+                .find(|mount| files.is_dir(mount))?,
+            proc_cgroup_path(&cgroups, Some("cpu"))?,
+        ),
+    };
+    let quota_at = |dir: &str| {
+        if files.exists(&format!("{}/cpu.max", dir)) {
+            files.parse_file(&format!("{}/cpu.max", dir), cgroup_cpu_max).ok().flatten()
+        } else {
+            let quota = files.value_from_file::<i64>(&format!("{}/cpu.cfs_quota_us", dir)).ok()?;
+            let period = files.value_from_file::<i64>(&format!("{}/cpu.cfs_period_us", dir)).ok()?;
+            Some(quota as f64 / period as f64).filter(|_| quota > 0 && period > 0)
+        }
+    };
+    // Limits of the ancestors apply too (as far as they're visible)
+    let mut path = path.trim_end_matches('/');
+    let mut result: Option<f64> = None;
+    loop {
+        if let Some(quota) = quota_at(&format!("{}{}", mount, path)) {
+            result = Some(result.map_or(quota, |result| result.min(quota)));
+        }
+        match path.rfind('/') {
+            Some(parent) => path = &path[..parent],
+            None => return result,
+        }
+    }
+}
+
+#[test]
+fn test_cgroup_cpu_quota() {
+    assert_eq!(cgroup_cpu_max("max 100000\n").unwrap().1, None);
+    assert_eq!(cgroup_cpu_max("150000 100000\n").unwrap().1, Some(1.5));
+    assert_eq!(proc_cgroup_path("0::/user.slice/session-2.scope\n", None), Some("/user.slice/session-2.scope"));
+    let v1 = "12:pids:/docker/ab12\n4:cpu,cpuacct:/docker/ab12\n1:name=systemd:/docker/ab12\n";
+    assert_eq!(proc_cgroup_path(v1, Some("cpu")), Some("/docker/ab12"));
+    assert_eq!(proc_cgroup_path(v1, None), None);
+
+    let dir = std::env::temp_dir().join(format!("systemstat-test-cpu-quota-{}", std::process::id()));
+    let write = |path: &str, data: &str| save(&dir.join(path), data.as_bytes()).unwrap();
+    write("proc/self/cgroup", "0::/system.slice/app.service\n");
+    write("sys/fs/cgroup/cgroup.controllers", "cpu memory pids\n");
+    write("sys/fs/cgroup/system.slice/cpu.max", "200000 100000\n");
+    write("sys/fs/cgroup/system.slice/app.service/cpu.max", "max 100000\n");
+    let files = Files::Replay(dir.clone());
+    assert_eq!(cgroup_cpu_quota(&files), Some(2.0));
+    write("sys/fs/cgroup/system.slice/app.service/cpu.max", "50000 100000\n");
+    assert_eq!(cgroup_cpu_quota(&files), Some(0.5));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(cgroup_cpu_quota(&files), None);
+}
+
+// Count a file descriptor by the target of its `/proc/<pid>/fd` link,
+// e.g. `/home/me/file`, `socket:[12345]`, `pipe:[12345]` or `anon_inode:[eventfd]`
+fn count_fd(fds: &mut ProcessFds, target: &str) {
+    if target.starts_with('/') {
+        fds.files += 1;
+    } else if target.starts_with("socket:") {
+        fds.sockets += 1;
+    } else if target.starts_with("pipe:") {
+        fds.pipes += 1;
+    } else if target.starts_with("anon_inode:") {
+        fds.anon_inodes += 1;
+    } else {
+        fds.other += 1;
+    }
+}
+
+#[test]
+fn test_count_fd() {
+    let mut fds = ProcessFds::default();
+    for target in ["/dev/null", "/home/me/log (deleted)", "socket:[52345]", "socket:[52346]", "pipe:[9871]",
+                   "anon_inode:[eventfd]", "anon_inode:inotify", "net:[4026531840]"] {
+        count_fd(&mut fds, target);
+    }
+    assert_eq!(fds, ProcessFds { files: 2, sockets: 2, pipes: 1, anon_inodes: 2, other: 1 });
+    assert_eq!(fds.total(), 8);
+}
+
+// Parse `/proc/<pid>/io`
+fn proc_pid_io(input: &str) -> IResult<&str, ProcessIo> {
+    map(
+        fold_many0(
+            tuple((ws(take_until(":")), preceded(tag(":"), u64_s))),
+            BTreeMap::new,
+            |mut map: BTreeMap<&str, u64>, (key, value)| {
+                map.insert(key, value);
+                map
+            },
+        ),
+        |map| {
+            let value = |key| map.get(key).copied().unwrap_or(0);
+            ProcessIo {
+                read_chars: ByteSize::b(value("rchar")),
+                written_chars: ByteSize::b(value("wchar")),
+                read_syscalls: value("syscr"),
+                write_syscalls: value("syscw"),
+                read_bytes: ByteSize::b(value("read_bytes")),
+                write_bytes: ByteSize::b(value("write_bytes")),
+                cancelled_write_bytes: ByteSize::b(value("cancelled_write_bytes")),
+            }
+        },
+    )(input)
+}
+
+#[test]
+fn test_proc_pid_io() {
+    let input = "rchar: 323934931
+wchar: 323929600
+syscr: 632687
+syscw: 632675
+read_bytes: 4096
+write_bytes: 323932160
+cancelled_write_bytes: 0
+";
+    let result = proc_pid_io(input).unwrap().1;
+    assert_eq!(result.read_chars, ByteSize::b(323934931));
+    assert_eq!(result.write_syscalls, 632675);
+    assert_eq!(result.read_bytes, ByteSize::b(4096));
+    assert_eq!(result.write_bytes, ByteSize::b(323932160));
+}
+
+// Summarize `/proc/<pid>/smaps_rollup` (parsed like `/proc/meminfo`)
+fn smaps_rollup_memory(rollup: &BTreeMap<String, ByteSize>) -> ProcessMemory {
+    let value = |key| rollup.get(key).copied().unwrap_or(ByteSize::b(0));
+    ProcessMemory {
+        rss: value("Rss"),
+        pss: value("Pss"),
+        uss: value("Private_Clean") + value("Private_Dirty"),
+        shared: value("Shared_Clean") + value("Shared_Dirty"),
+        swap: value("Swap"),
+        swap_pss: value("SwapPss"),
+    }
+}
+
+#[test]
+fn test_smaps_rollup() {
+    let input = "55d2b1a3c000-7ffd9e5fe000 ---p 00000000 00:00 0                          [rollup]
+Rss:                6060 kB
+Pss:                1491 kB
+Pss_Dirty:           852 kB
+Shared_Clean:       4896 kB
+Shared_Dirty:        308 kB
+Private_Clean:         4 kB
+Private_Dirty:       852 kB
+Referenced:         6060 kB
+Anonymous:           852 kB
+Swap:                 16 kB
+SwapPss:               8 kB
+Locked:                0 kB
+";
+    let result = smaps_rollup_memory(&proc_meminfo(input).unwrap().1);
+    assert_eq!(result.rss, ByteSize::kib(6060));
+    assert_eq!(result.pss, ByteSize::kib(1491));
+    assert_eq!(result.uss, ByteSize::kib(856));
+    assert_eq!(result.shared, ByteSize::kib(5204));
+    assert_eq!(result.swap, ByteSize::kib(16));
+    assert_eq!(result.swap_pss, ByteSize::kib(8));
+}
+
+// Guess the device a partition is on from its name: `sda1` is on `sda`, `nvme0n1p2` on `nvme0n1`
+fn partition_parent(name: &str) -> Option<&str> {
+    let disk = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if disk.len() == name.len() {
+        return None;
+    }
+    match disk.strip_suffix('p') {
+        Some(prefix) if prefix.ends_with(|c: char| c.is_ascii_digit()) => Some(prefix),
+        _ => Some(disk),
+    }
+}
+
+// Classify a block device that isn't a partition by its name
+fn block_device_kind(name: &str) -> BlockDeviceKind {
+    if name.starts_with("loop") {
+        BlockDeviceKind::Loop
+    } else if name.starts_with("zram") {
+        BlockDeviceKind::Zram
+    } else if name.starts_with("ram") {
+        BlockDeviceKind::Ram
+    } else if name.starts_with("dm-") {
+        BlockDeviceKind::DeviceMapper
+    } else if name.starts_with("md") {
+        BlockDeviceKind::Md
+    } else if name.starts_with("nvme") {
+        BlockDeviceKind::NvmeNamespace
+    } else if matches!(name.strip_prefix("sr"), Some(n) if !n.is_empty() && n.bytes().all(|c| c.is_ascii_digit())) {
+        BlockDeviceKind::Optical
+    } else {
+        BlockDeviceKind::Disk
+    }
+}
+
+fn block_device_statistics(files: &Files) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
+    let mut result: BTreeMap<String, BlockDeviceStats> = BTreeMap::new();
+    let stats: Vec<BlockDeviceStats> = files.parse_file("/proc/diskstats", proc_diskstats)?;
+
+    for blkstats in stats {
+        result.entry(blkstats.name.clone()).or_insert(blkstats);
+    }
+    let names: BTreeSet<String> = result.keys().cloned().collect();
+    for blkstats in result.values_mut() {
+        classify_block_device(files, &names, blkstats);
+    }
+    Ok(result)
+}
+
+/// Set the kind and parent of a block device, given the names of all the block devices
+fn classify_block_device(files: &Files, names: &BTreeSet<String>, blkstats: &mut BlockDeviceStats) {
+    let parent = partition_parent(&blkstats.name)
+        .filter(|parent| names.contains(*parent))
+        .map(str::to_string);
+    // sysfs knows for sure, the names are the fallback
+    let sysfs = format!("/sys/class/block/{}", blkstats.name);
+    let partition = if files.exists(&sysfs) {
+        files.exists(&format!("{}/partition", sysfs))
+    } else {
+        parent.is_some()
+    };
+    if partition {
+        blkstats.kind = BlockDeviceKind::Partition;
+        blkstats.parent = parent;
+    } else {
+        blkstats.kind = block_device_kind(&blkstats.name);
+        blkstats.parent = None;
+    }
+}
+
+#[test]
+fn test_block_device_kind() {
+    assert_eq!(partition_parent("sda1"), Some("sda"));
+    assert_eq!(partition_parent("nvme0n1p12"), Some("nvme0n1"));
+    assert_eq!(partition_parent("mmcblk0p1"), Some("mmcblk0"));
+    assert_eq!(partition_parent("sda"), None);
+    assert_eq!(block_device_kind("nvme0n1"), BlockDeviceKind::NvmeNamespace);
+    assert_eq!(block_device_kind("zram0"), BlockDeviceKind::Zram);
+    assert_eq!(block_device_kind("ram15"), BlockDeviceKind::Ram);
+    assert_eq!(block_device_kind("md127"), BlockDeviceKind::Md);
+    assert_eq!(block_device_kind("vda"), BlockDeviceKind::Disk);
+    assert_eq!(block_device_kind("sr0"), BlockDeviceKind::Optical);
+    assert_eq!(block_device_kind("sr"), BlockDeviceKind::Disk);
+
+    // Without sysfs, partitions are recognized by name
+    let dir = std::env::temp_dir().join(format!("systemstat-test-diskstats-{}", std::process::id()));
+    save(
+        &dir.join("proc/diskstats"),
+        b"   8       0 sda 10 0 80 5 4 0 32 6 0 11 11\n   8       1 sda1 8 0 64 4 4 0 32 6 0 10 10\n\
+             7       0 loop0 3 0 6 0 0 0 0 0 0 1 1\n 253       0 dm-0 2 0 16 1 4 0 32 6 0 7 7\n",
+    )
+    .unwrap();
+    let stats = block_device_statistics(&Files::Replay(dir.clone())).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(stats["sda"].kind, BlockDeviceKind::Disk);
+    assert_eq!(stats["sda1"].kind, BlockDeviceKind::Partition);
+    assert_eq!(stats["sda1"].parent.as_deref(), Some("sda"));
+    assert_eq!(stats["loop0"].kind, BlockDeviceKind::Loop);
+    assert_eq!(stats["dm-0"].kind, BlockDeviceKind::DeviceMapper);
+}
+
+// Convert a signal or noise level to dBm: old drivers report it as an unsigned byte,
+// and -256 (or 0 for noise) means it's not available
+fn wireless_dbm(level: f32) -> Option<f32> {
+    if level <= -256.0 {
+        None
+    } else if level > 0.0 {
+        Some(level - 256.0)
+    } else {
+        Some(level)
+    }
+}
+
+// Build `WirelessStats` from the values of `/proc/net/wireless` or `/sys/class/net/<if>/wireless`,
+// in their order: link, level, noise, then the discarded packet and missed beacon counters
+fn wireless_stats(quality: [f32; 3], counters: [u64; 6]) -> WirelessStats {
+    let [link, level, noise] = quality;
+    let [nwid, crypt, frag, retry, misc, beacon] = counters;
+    WirelessStats {
+        link_quality: link,
+        signal_dbm: wireless_dbm(level),
+        noise_dbm: wireless_dbm(noise).filter(|_| noise != 0.0),
+        discarded_nwid: nwid,
+        discarded_crypt: crypt,
+        discarded_frag: frag,
+        discarded_retry: retry,
+        discarded_misc: misc,
+        missed_beacons: beacon,
+    }
+}
+
+// Parse `/proc/net/wireless`. A `.` after a quality value means it was updated since last read.
+fn proc_net_wireless(input: &str) -> Option<BTreeMap<String, WirelessStats>> {
+    let mut result = BTreeMap::new();
+    // Two lines of headers
+    for line in input.lines().skip(2).filter(|line| !line.trim().is_empty()) {
+        let (name, values) = line.split_once(':')?;
+        let mut fields = values.split_ascii_whitespace().skip(1); // status
+        let mut quality = [0.0; 3];
+        for value in quality.iter_mut() {
+            *value = fields.next()?.trim_end_matches('.').parse().ok()?;
+        }
+        let mut counters = [0; 6];
+        for value in counters.iter_mut() {
+            *value = fields.next()?.parse().ok()?;
+        }
+        result.insert(name.trim().to_string(), wireless_stats(quality, counters));
+    }
+    Some(result)
+}
+
+#[test]
+fn test_proc_net_wireless() {
+    let input = "Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+wlp2s0: 0000   58.  -52.  -256        0      0      0     12      3        7
+ wlan1: 0000   30   186    161        0      1      0      0      0        0
+ wlan2: 0000    0  -256   -256        0      0      0      0      0        0
+";
+    let result = proc_net_wireless(input).unwrap();
+    assert_eq!(result.len(), 3);
+    let wlp2s0 = &result["wlp2s0"];
+    assert_eq!((wlp2s0.link_quality, wlp2s0.signal_dbm, wlp2s0.noise_dbm), (58.0, Some(-52.0), None));
+    assert_eq!((wlp2s0.discarded_retry, wlp2s0.discarded_misc, wlp2s0.missed_beacons), (12, 3, 7));
+    let wlan1 = &result["wlan1"];
+    assert_eq!((wlan1.signal_dbm, wlan1.noise_dbm, wlan1.discarded_crypt), (Some(-70.0), Some(-95.0), 1));
+    assert_eq!((result["wlan2"].signal_dbm, result["wlan2"].noise_dbm), (None, None));
+    assert_eq!(proc_net_wireless(&input[..input.find("wlp2s0").unwrap()]), Some(BTreeMap::new()));
+    assert!(proc_net_wireless("\n\nwlan0: 0000 1 2\n").is_none());
+
+    // Without `/proc/net/wireless`, from sysfs
+    let dir = std::env::temp_dir().join(format!("systemstat-test-wireless-{}", std::process::id()));
+    let values = [("link", "40"), ("level", "-60"), ("noise", "-256"), ("nwid", "0"), ("crypt", "0"),
+                  ("fragment", "0"), ("retries", "5"), ("misc", "0"), ("beacon", "2")];
+    for (file, value) in values.iter() {
+        save(&dir.join("sys/class/net/wlan0/wireless").join(file), value.as_bytes()).unwrap();
+    }
+    save(&dir.join("sys/class/net/eth0/address"), b"00:00:00:00:00:00\n").unwrap();
+    let result = PlatformImpl::replay(&dir).wireless().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(result.keys().collect::<Vec<_>>(), vec!["wlan0"]);
+    assert_eq!((result["wlan0"].signal_dbm, result["wlan0"].discarded_retry, result["wlan0"].missed_beacons), (Some(-60.0), 5, 2));
+}
+
+/// Check whether the kernel has wireless extensions or any interface is wireless
+fn has_wireless(files: &Files) -> bool {
+    files.exists("/proc/net/wireless")
+        || files
+            .read_dir("/sys/class/net")
+            .unwrap_or_default()
+            .iter()
+            .any(|name| files.is_dir(&format!("/sys/class/net/{}/wireless", name)))
+}
+
+/// Read the statistics of a wireless interface from `/sys/class/net/<if>/wireless`
+fn sysfs_wireless(files: &Files, dir: &str) -> io::Result<WirelessStats> {
+    let mut quality = [0.0; 3];
+    for (value, file) in quality.iter_mut().zip(&["link", "level", "noise"]) {
+        *value = files.value_from_file(&format!("{}/{}", dir, file))?;
+    }
+    let mut counters = [0; 6];
+    for (value, file) in counters.iter_mut().zip(&["nwid", "crypt", "fragment", "retries", "misc", "beacon"]) {
+        *value = files.value_from_file(&format!("{}/{}", dir, file))?;
+    }
+    Ok(wireless_stats(quality, counters))
+}
+
+/// Get the number of clock ticks per second, the unit of CPU times in procfs
+fn clock_ticks(files: &Files) -> io::Result<u64> {
+    let live = || match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => Ok(format!("{}\n", ticks)),
+        _ => Err(io::Error::last_os_error()),
+    };
+    files.parse_record("clock_ticks", live, u64_s)
+}
+
+// Convert a number of clock ticks into a duration
+fn ticks_duration(ticks: u64, per_sec: u64) -> Duration {
+    Duration::from_secs(ticks / per_sec) + Duration::from_nanos((ticks % per_sec) * 1_000_000_000 / per_sec)
+}
+
+// Parse `/etc/passwd` into usernames by user ID
+fn passwd(input: &str) -> BTreeMap<u32, String> {
+    input
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+// Convert the state letter of `/proc/<pid>/stat`
+fn process_state(code: &str) -> ProcessState {
+    match code {
+        "R" => ProcessState::Running,
+        "S" => ProcessState::Sleeping,
+        "D" => ProcessState::DiskSleep,
+        "T" => ProcessState::Stopped,
+        "t" => ProcessState::TracingStop,
+        "Z" => ProcessState::Zombie,
+        "X" | "x" => ProcessState::Dead,
+        "I" => ProcessState::Idle,
+        _ => ProcessState::Unknown,
+    }
+}
+
+/// The fields we need from `/proc/<pid>/stat` or `/proc/<pid>/task/<tid>/stat`
+struct ProcPidStat {
+    pid: u32,
+    name: String,
+    state: ProcessState,
+    ppid: u32,
+    /// In clock ticks
+    utime: u64,
+    stime: u64,
+    /// The CPU it last ran on
+    processor: Option<usize>,
+}
+
+// Parse `/proc/<pid>/stat`. The name is in parentheses but can contain anything,
+// including spaces and parentheses, so it ends at the last `)`
+fn proc_pid_stat(input: &str) -> Option<ProcPidStat> {
+    let (pid, rest) = input.split_once(" (")?;
+    let (name, rest) = rest.rsplit_once(") ")?;
+    // Starting with the 3rd field in proc(5)
+    let fields = rest.split_whitespace().collect::<Vec<_>>();
+    Some(ProcPidStat {
+        pid: pid.trim().parse().ok()?,
+        name: name.to_string(),
+        state: process_state(fields.first()?),
+        ppid: fields.get(1)?.parse().ok()?,
+        utime: fields.get(11)?.parse().ok()?,
+        stime: fields.get(12)?.parse().ok()?,
+        processor: fields.get(36).and_then(|field| field.parse().ok()),
+    })
+}
+
+// Get the real user ID and the resident set size from `/proc/<pid>/status`
+fn proc_pid_status(input: &str) -> (Option<u32>, ByteSize) {
+    let mut uid = None;
+    let mut rss = ByteSize::b(0);
+    for line in input.lines() {
+        if let Some(uids) = line.strip_prefix("Uid:") {
+            uid = uids.split_whitespace().next().and_then(|uid| uid.parse().ok());
+        } else if let Some(kib) = line.strip_prefix("VmRSS:") {
+            rss = ByteSize::kib(kib.trim().trim_end_matches("kB").trim().parse().unwrap_or(0));
+        }
+    }
+    (uid, rss)
+}
+
+/// Read `/proc/<pid>/stat` (or the stat file of a thread)
+fn read_pid_stat(files: &Files, path: &str) -> io::Result<ProcPidStat> {
+    proc_pid_stat(&files.read_file(path)?).ok_or_else(|| {
+        Error::Parse {
+            path: path.to_string(),
+            line: Some(1),
+            message: "Unexpected format".to_string(),
+        }
+        .into()
+    })
+}
+
+/// Read the information about a process from `/proc/<pid>`
+fn read_process(files: &Files, pid: u32, ticks: u64, users: &BTreeMap<u32, String>) -> io::Result<Process> {
+    let stat = read_pid_stat(files, &format!("/proc/{}/stat", pid))?;
+    let (uid, rss) = proc_pid_status(&files.read_file(&format!("/proc/{}/status", pid))?);
+    let uid = uid.ok_or_else(|| Error::Parse {
+        path: format!("/proc/{}/status", pid),
+        line: None,
+        message: "Missing Uid".to_string(),
+    })?;
+    let cgroup = files.read_file(&format!("/proc/{}/cgroup", pid)).ok().and_then(|data| {
+        proc_cgroup_path(&data, None)
+            .or_else(|| proc_cgroup_path(&data, Some("cpu")))
+            .map(str::to_string)
+    });
+    Ok(Process {
+        pid: stat.pid,
+        ppid: stat.ppid,
+        name: stat.name,
+        state: stat.state,
+        uid,
+        username: users.get(&uid).cloned(),
+        cgroup,
+        user_time: ticks_duration(stat.utime, ticks),
+        system_time: ticks_duration(stat.stime, ticks),
+        rss,
+    })
+}
+
+/// Read the threads of a process from `/proc/<pid>/task`
+fn threads(files: &Files, pid: u32) -> io::Result<Vec<Thread>> {
+    let ticks = clock_ticks(files)?;
+    let dir = format!("/proc/{}/task", pid);
+    let mut result = Vec::new();
+    for name in files.read_dir(&dir)? {
+        // Skip threads that exit while being read
+        if let Ok(stat) = read_pid_stat(files, &format!("{}/{}/stat", dir, name)) {
+            // Prefer `comm`, falling back to the name in `stat` if the thread exits in between
+            let name = files
+                .read_file(&format!("{}/{}/comm", dir, name))
+                .map(|comm| comm.trim_end_matches('\n').to_string())
+                .unwrap_or(stat.name);
+            result.push(Thread {
+                tid: stat.pid,
+                name,
+                state: stat.state,
+                last_cpu: stat.processor,
+                user_time: ticks_duration(stat.utime, ticks),
+                system_time: ticks_duration(stat.stime, ticks),
+            });
+        }
+    }
+    Ok(result)
+}
+
+#[test]
+fn test_proc_pid_stat() {
+    let input = "4134 (tmux: server) S 1 4134 4134 0 -1 4194624 13707 162 0 0 2155 1493 0 0 20 0 1 0 1645 \
+                 13111296 1378 18446744073709551615 1 1 0 0 0 0 0 3674116 1266777607 0 0 0 17 5 0 0 0 0 0\n";
+    let stat = proc_pid_stat(input).unwrap();
+    assert_eq!((stat.pid, stat.name.as_str(), stat.ppid), (4134, "tmux: server", 1));
+    assert_eq!(stat.state, ProcessState::Sleeping);
+    assert_eq!((stat.utime, stat.stime, stat.processor), (2155, 1493, Some(5)));
+    let stat = proc_pid_stat("77 (a) b (c)) R 2 0 0 0 -1 0 0 0 0 0 7 8\n").unwrap();
+    assert_eq!((stat.name.as_str(), stat.state, stat.utime, stat.processor), ("a) b (c)", ProcessState::Running, 7, None));
+    assert!(proc_pid_stat("77 (a) R\n").is_none());
+    assert_eq!(ticks_duration(2155, 100), Duration::from_millis(21550));
+
+    let status = "Name:\tbash\nUmask:\t0022\nState:\tS (sleeping)\nUid:\t1000\t1000\t1000\t1000\n\
+                  Gid:\t1000\t1000\t1000\t1000\nVmRSS:\t    5376 kB\n";
+    assert_eq!(proc_pid_status(status), (Some(1000), ByteSize::kib(5376)));
+    assert_eq!(proc_pid_status("Name:\tkthreadd\nUid:\t0\t0\t0\t0\n"), (Some(0), ByteSize::b(0)));
+    let users = passwd("root:x:0:0:root:/root:/bin/bash\n# comment\nwww-data:x:33:33::/var/www:/usr/sbin/nologin\n");
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[&33], "www-data");
+}
+
+// Parse a line of text, including the line break
+fn line_s(input: &str) -> IResult<&str, &str> {
+    terminated(not_line_ending, tag("\n"))(input)
+}
+
+// Format an address for the network interfaces record
+fn ip_record(addr: &IpAddr) -> String {
+    match addr {
+        IpAddr::Empty => "-".to_string(),
+        IpAddr::Unsupported => "?".to_string(),
+        IpAddr::V4(addr) => addr.to_string(),
+        IpAddr::V6(addr) => addr.to_string(),
+    }
+}
+
+// Parse an address of the network interfaces record
+fn parse_ip_record(input: &str) -> Result<IpAddr, std::net::AddrParseError> {
+    match input {
+        "-" => Ok(IpAddr::Empty),
+        "?" => Ok(IpAddr::Unsupported),
+        _ => input.parse().map(|addr| match addr {
+            std::net::IpAddr::V4(addr) => IpAddr::V4(addr),
+            std::net::IpAddr::V6(addr) => IpAddr::V6(addr),
+        }),
+    }
+}
+
+// Format the network interfaces as a record, one per line:
+// the name followed by pairs of address and netmask
+fn networks_record(networks: &BTreeMap<String, Network>) -> String {
+    networks
+        .values()
+        .map(|network| {
+            let mut line = network.name.clone();
+            for addrs in &network.addrs {
+                line += &format!(" {} {}", ip_record(&addrs.addr), ip_record(&addrs.netmask));
+            }
+            line + "\n"
+        })
+        .collect()
+}
+
+// Parse a line of the network interfaces record
+fn network_record(input: &str) -> Result<Network, std::net::AddrParseError> {
+    let mut fields = input.split(' ');
+    let name = fields.next().unwrap_or_default().to_string();
+    let fields = fields.collect::<Vec<_>>();
+    let addrs = fields
+        .chunks(2)
+        .map(|pair| {
+            Ok(NetworkAddrs {
+                addr: parse_ip_record(pair[0])?,
+                netmask: parse_ip_record(pair.get(1).copied().unwrap_or_default())?,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Network { name, addrs })
+}
+
+// Parse the network interfaces record
+fn networks_record_s(input: &str) -> IResult<&str, BTreeMap<String, Network>> {
+    fold_many0(
+        map_res(line_s, network_record),
+        BTreeMap::new,
+        |mut map, network| {
+            map.insert(network.name.clone(), network);
+            map
+        },
+    )(input)
+}
+
+#[test]
+fn test_networks_record() {
+    let mut networks = BTreeMap::new();
+    networks.insert("lo".to_string(), Network {
+        name: "lo".to_string(),
+        addrs: vec![
+            NetworkAddrs { addr: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), netmask: IpAddr::V4(Ipv4Addr::new(255, 0, 0, 0)) },
+            NetworkAddrs { addr: IpAddr::V6(Ipv6Addr::LOCALHOST), netmask: IpAddr::Empty },
+        ],
+    });
+    networks.insert("wg0".to_string(), Network { name: "wg0".to_string(), addrs: Vec::new() });
+    let record = networks_record(&networks);
+    assert_eq!(record, "lo 127.0.0.1 255.0.0.0 ::1 -\nwg0\n");
+    let result = networks_record_s(&record).unwrap().1;
+    assert_eq!(result.len(), 2);
+    assert_eq!(result["lo"].addrs[0].netmask, IpAddr::V4(Ipv4Addr::new(255, 0, 0, 0)));
+    assert_eq!(result["lo"].addrs[1].addr, IpAddr::V6(Ipv6Addr::LOCALHOST));
+    assert!(result["wg0"].addrs.is_empty());
+}
+
+// Format the kernel information from `uname` as a record, one field per line
+fn uname_record(info: &OsInfo) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}\n",
+        info.hostname, info.kernel_name, info.kernel_release, info.kernel_version, info.machine
+    )
+}
+
+// Parse the `uname` record
+fn uname_record_s(input: &str) -> IResult<&str, OsInfo> {
+    map(
+        tuple((line_s, line_s, line_s, line_s, line_s)),
+        |(hostname, kernel_name, kernel_release, kernel_version, machine)| OsInfo {
+            hostname: hostname.to_string(),
+            kernel_name: kernel_name.to_string(),
+            kernel_release: kernel_release.to_string(),
+            kernel_version: kernel_version.to_string(),
+            machine: machine.to_string(),
+            distribution: None,
+            virtualization: None,
+        },
+    )(input)
+}
+
+/// A file in procfs that's kept open and read again from the start, into the same buffer.
+struct ProcFile {
+    path: &'static str,
+    file: fs::File,
+    buf: Vec<u8>,
+}
+
+impl ProcFile {
+    fn open(path: &'static str) -> io::Result<ProcFile> {
+        Ok(ProcFile { path, file: fs::File::open(path)?, buf: Vec::with_capacity(4096) })
+    }
+
+    fn read(&mut self) -> io::Result<&str> {
+        self.file.seek(SeekFrom::Start(0))?;
+        self.buf.clear();
+        self.file.read_to_end(&mut self.buf)?;
+        str::from_utf8(&self.buf).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn parse_error(&self) -> io::Error {
+        Error::Parse {
+            path: self.path.to_string(),
+            line: None,
+            message: "Unexpected format".to_string(),
+        }
+        .into()
+    }
+}
+
+// Update per-CPU times in place from `/proc/stat`, without allocating unless there are more CPUs
+fn update_cpu_times(input: &str, times: &mut Vec<(usize, CpuTime)>) -> Option<()> {
+    let mut count = 0;
+    // The CPU lines come first, the aggregate `cpu` line has no ID
+    for line in input.lines().skip(1).take_while(|line| line.starts_with("cpu")) {
+        let mut fields = line["cpu".len()..].split_ascii_whitespace().map(str::parse::<usize>);
+        let mut next = || fields.next()?.ok();
+        let (cpu, user, nice, system, idle, iowait, irq) = (next()?, next()?, next()?, next()?, next()?, next()?, next()?);
+        let entry = (cpu, CpuTime { user, nice, system, idle, interrupt: irq, other: iowait });
+        match times.get_mut(count) {
+            Some(time) => *time = entry,
+            None => times.push(entry),
+        }
+        count += 1;
+    }
+    times.truncate(count);
+    Some(())
+}
+
+// Update block device statistics in place from `/proc/diskstats`, returning whether the list
+// of devices changed. Only new or renamed devices allocate.
+fn update_diskstats(input: &str, devices: &mut Vec<BlockDeviceStats>) -> Option<bool> {
+    let mut count = 0;
+    let mut changed = false;
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        let mut fields = line.split_ascii_whitespace().skip(2);
+        let name = fields.next()?;
+        let mut values = [0; 11];
+        for value in values.iter_mut() {
+            *value = fields.next()?.parse().ok()?;
+        }
+        // Keep the first of duplicate names, like `block_device_statistics`
+        if devices[..count].iter().any(|device| device.name == name) {
+            continue;
+        }
+        if count == devices.len() {
+            devices.push(BlockDeviceStats::sum(name, &[]));
+            changed = true;
+        }
+        let device = &mut devices[count];
+        if device.name != name {
+            device.name.clear();
+            device.name.push_str(name);
+            changed = true;
+        }
+        let [
+            read_ios,
+            read_merges,
+            read_sectors,
+            read_ticks,
+            write_ios,
+            write_merges,
+            write_sectors,
+            write_ticks,
+            in_flight,
+            io_ticks,
+            time_in_queue,
+        ] = values;
+        device.read_ios = read_ios;
+        device.read_merges = read_merges;
+        device.read_sectors = read_sectors;
+        device.read_ticks = read_ticks;
+        device.write_ios = write_ios;
+        device.write_merges = write_merges;
+        device.write_sectors = write_sectors;
+        device.write_ticks = write_ticks;
+        device.in_flight = in_flight;
+        device.io_ticks = io_ticks;
+        device.time_in_queue = time_in_queue;
+        count += 1;
+    }
+    changed |= devices.len() != count;
+    devices.truncate(count);
+    Some(changed)
+}
+
+/// Low-overhead repeated reading of the per-CPU times and the block device statistics,
+/// for polling at a high frequency.
+///
+/// The files are kept open, and the buffers and the parsed values are reused,
+/// so that once the first call has sized everything, calls don't allocate
+/// (except when block devices are added or renamed).
+///
+/// ```no_run
+/// use std::{thread, time::Duration};
+/// use systemstat::Poller;
+///
+/// let mut poller = Poller::new().unwrap();
+/// loop {
+///     thread::sleep(Duration::from_millis(250));
+///     let busy = poller.cpu_load().unwrap().iter().filter(|cpu| cpu.idle < 0.1).count();
+///     let reads: usize = poller.disk_statistics().unwrap().map(|disk| disk.read_ios).sum();
+///     println!("{} busy CPUs, {} reads", busy, reads);
+/// }
+/// ```
+pub struct Poller {
+    stat: ProcFile,
+    diskstats: ProcFile,
+    cpu_times: Vec<(usize, CpuTime)>,
+    prev_cpu_times: Vec<(usize, CpuTime)>,
+    cpu_load: Vec<CPULoad>,
+    devices: Vec<BlockDeviceStats>,
+}
+
+impl Poller {
+    /// Opens `/proc/stat` and `/proc/diskstats`.
+    pub fn new() -> io::Result<Poller> {
+        Ok(Poller {
+            stat: ProcFile::open("/proc/stat")?,
+            diskstats: ProcFile::open("/proc/diskstats")?,
+            cpu_times: Vec::new(),
+            prev_cpu_times: Vec::new(),
+            cpu_load: Vec::new(),
+            devices: Vec::new(),
+        })
+    }
+
+    /// Reads the current per-CPU times, with the CPU IDs.
+    pub fn cpu_times(&mut self) -> io::Result<&[(usize, CpuTime)]> {
+        mem::swap(&mut self.cpu_times, &mut self.prev_cpu_times);
+        let result = match self.stat.read() {
+            Ok(data) => update_cpu_times(data, &mut self.cpu_times).ok_or_else(|| self.stat.parse_error()),
+            Err(err) => Err(err),
+        };
+        if result.is_err() {
+            // Keep the last good sample for the next call to measure from
+            mem::swap(&mut self.cpu_times, &mut self.prev_cpu_times);
+        }
+        result?;
+        Ok(&self.cpu_times)
+    }
+
+    /// Returns the per-CPU load since the previous call (of this or `cpu_times`),
+    /// in the same order as `cpu_load()`. The first call returns the load since boot.
+    pub fn cpu_load(&mut self) -> io::Result<&[CPULoad]> {
+        self.cpu_times()?;
+        let zero = CpuTime { user: 0, nice: 0, system: 0, interrupt: 0, idle: 0, other: 0 };
+        let prev = &self.prev_cpu_times;
+        self.cpu_load.clear();
+        self.cpu_load.extend(self.cpu_times.iter().enumerate().map(|(i, (cpu, time))| {
+            // CPUs can go offline and online in between
+            let prev = prev.get(i).filter(|(prev_cpu, _)| prev_cpu == cpu).map_or(&zero, |(_, time)| time);
+            (*time - prev).to_cpuload()
+        }));
+        Ok(&self.cpu_load)
+    }
+
+    /// Reads the statistics of all the block devices, in the order of `/proc/diskstats`.
+    pub fn block_device_statistics(&mut self) -> io::Result<&[BlockDeviceStats]> {
+        let data = self.diskstats.read()?;
+        match update_diskstats(data, &mut self.devices) {
+            Some(true) => {
+                let names = self.devices.iter().map(|device| device.name.clone()).collect();
+                for device in self.devices.iter_mut() {
+                    classify_block_device(&Files::Live, &names, device);
+                }
+            }
+            Some(false) => (),
+            None => return Err(self.diskstats.parse_error()),
+        }
+        Ok(&self.devices)
+    }
+
+    /// Same as `block_device_statistics`, but only for physical disks
+    /// (see `Platform::disk_statistics`).
+    pub fn disk_statistics(&mut self) -> io::Result<impl Iterator<Item = &BlockDeviceStats>> {
+        Ok(self.block_device_statistics()?.iter().filter(|device| device.kind.is_physical_disk()))
+    }
+}
+
+#[test]
+fn test_poller_parsers() {
+    let stat = "cpu  10 0 10 100 1 0 0 0 0 0\ncpu0 5 0 5 50 1 0 0 0 0 0\ncpu2 5 0 5 50 0 2 0 0 0 0\nintr 1 2 3\n";
+    let mut times = vec![(7, CpuTime { user: 1, nice: 1, system: 1, interrupt: 1, idle: 1, other: 1 }); 3];
+    update_cpu_times(stat, &mut times).unwrap();
+    assert_eq!(times.len(), 2);
+    assert_eq!(times[1].0, 2);
+    assert_eq!((times[1].1.user, times[1].1.idle, times[1].1.interrupt, times[1].1.other), (5, 50, 2, 0));
+    assert!(update_cpu_times("cpu  1\ncpu0 1 2\n", &mut times).is_none());
+    let parsed = proc_stat_cpu_times(stat).unwrap().1;
+    assert_eq!(parsed.len(), times.len());
+
+    let diskstats = "   8       0 sda 10 1 80 5 4 2 32 6 0 11 12 0 0 0 0\n   8       1 sda1 8 0 64 4 4 0 32 6 0 10 10\n";
+    let mut devices = Vec::new();
+    assert_eq!(update_diskstats(diskstats, &mut devices), Some(true));
+    let expected = proc_diskstats(diskstats).unwrap().1;
+    assert_eq!(format!("{:?}", devices), format!("{:?}", expected));
+    let capacity = devices[0].name.capacity();
+    assert_eq!(update_diskstats(diskstats, &mut devices), Some(false));
+    assert_eq!(devices[0].name.capacity(), capacity);
+    assert_eq!(update_diskstats("   8       0 sdb 1 2 3 4 5 6 7 8 9 10 11\n", &mut devices), Some(true));
+    assert_eq!((devices.len(), devices[0].name.as_str(), devices[0].time_in_queue), (1, "sdb", 11));
+    assert!(update_diskstats("   8       0 sdb 1 2 3\n", &mut devices).is_none());
+    let duplicated = "   8       0 sdc 1 2 3 4 5 6 7 8 9 10 11\n   8       0 sdc 2 2 3 4 5 6 7 8 9 10 11\n";
+    assert_eq!(update_diskstats(duplicated, &mut devices), Some(true));
+    assert_eq!((devices.len(), devices[0].read_ios), (1, 1));
+}
+
+#[test]
+fn test_poller() {
+    let mut poller = Poller::new().unwrap();
+    let cpus = poller.cpu_times().unwrap().len();
+    assert_eq!(cpus, cpu_time(&Files::Live).unwrap().len());
+    assert_eq!(poller.cpu_load().unwrap().len(), cpus);
+    let devices = poller.block_device_statistics().unwrap().len();
+    assert_eq!(devices, block_device_statistics(&Files::Live).unwrap().len());
+    assert!(poller.disk_statistics().unwrap().all(|disk| disk.kind.is_physical_disk()));
+}
+
+pub struct PlatformImpl {
+    files: Files,
+}
+
+impl PlatformImpl {
+    /// Reads from the running system, like `new`, and also copies every file that's read
+    /// (and the results of the few system calls that are used) into `dir`,
+    /// to be read back later with `replay`, e.g. as a test fixture or for a bug report.
+    ///
+    /// Note that checking whether a file exists or listing a directory also writes into `dir`,
+    /// creating empty placeholders for the entries that were found, so that they are found again
+    /// when replaying.
+    pub fn capture<P: Into<PathBuf>>(dir: P) -> PlatformImpl {
+        PlatformImpl { files: Files::Capture(dir.into()) }
+    }
+
+    /// Reads from a directory filled by `capture` instead of the running system.
+    /// Anything that wasn't captured fails with a "not found" error.
+    ///
+    /// A capture only keeps the last contents of each file, so delayed measurements
+    /// (`cpu_load`, `interrupt_rates`, `disk_io_rates` etc.) can't be replayed meaningfully:
+    /// both samples are read from the same files, so nothing changes between them.
+    pub fn replay<P: Into<PathBuf>>(dir: P) -> PlatformImpl {
+        PlatformImpl { files: Files::Replay(dir.into()) }
+    }
+
+    /// Returns a vector of user sessions read from a given utmp database file,
+    /// e.g. one copied from another machine.
+    pub fn users_from_file<P: AsRef<path::Path>>(&self, path: P) -> io::Result<Vec<UserSession>> {
+        fs::read(path).map(|data| utmp(&data))
+    }
+}
+
+/// Reads from the running system, like `new`.
+impl Default for PlatformImpl {
+    fn default() -> PlatformImpl {
+        PlatformImpl::new()
+    }
+}
 
 /// An implementation of `Platform` for Linux.
 /// See `Platform` for documentation.
 impl Platform for PlatformImpl {
     #[inline(always)]
     fn new() -> Self {
-        PlatformImpl
+        PlatformImpl { files: Files::Live }
     }
 
     fn cpu_load(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
-        cpu_time().map(|times| {
-            DelayedMeasurement::new(Box::new(move || {
-                cpu_time().map(|delay_times| {
-                    delay_times
-                        .iter()
-                        .zip(times.iter())
-                        .map(|(now, prev)| (*now - prev).to_cpuload())
-                        .collect::<Vec<_>>()
-                })
-            }))
-        })
+        measure_cpu_load(&self.files, |_| true)
     }
 
     fn load_average(&self) -> io::Result<LoadAverage> {
-        unix::load_average()
+        self.files.parse_file("/proc/loadavg", proc_loadavg)
     }
 
     fn memory(&self) -> io::Result<Memory> {
-        PlatformMemory::new().map(PlatformMemory::to_memory)
+        PlatformMemory::new(&self.files).map(PlatformMemory::to_memory)
     }
 
     fn swap(&self) -> io::Result<Swap> {
-        PlatformMemory::new().map(PlatformMemory::to_swap)
+        PlatformMemory::new(&self.files).map(PlatformMemory::to_swap)
     }
 
     fn memory_and_swap(&self) -> io::Result<(Memory, Swap)> {
-        let pm = PlatformMemory::new()?;
+        let pm = PlatformMemory::new(&self.files)?;
         Ok((pm.clone().to_memory(), pm.to_swap()))
     }
 
     fn uptime(&self) -> io::Result<Duration> {
+        let uptime = self.files.parse_file("/proc/uptime", proc_uptime);
+        if uptime.is_ok() || self.files.is_replay() {
+            return uptime;
+        }
         let mut info: sysinfo = unsafe { mem::zeroed() };
         unsafe { sysinfo(&mut info) };
         Ok(Duration::from_secs(info.uptime as u64))
     }
 
     fn boot_time(&self) -> io::Result<OffsetDateTime> {
-        read_file("/proc/stat").and_then(|data| {
+        self.files.read_file("/proc/stat").and_then(|data| {
+            let parse_error = |line, message: String| -> io::Error {
+                Error::Parse {
+                    path: "/proc/stat".to_string(),
+                    line,
+                    message,
+                }
+                .into()
+            };
             data.lines()
-                .find(|line| line.starts_with("btime "))
-                .ok_or(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "Could not find btime in /proc/stat",
-                ))
-                .and_then(|line| {
+                .enumerate()
+                .find(|(_, line)| line.starts_with("btime "))
+                .ok_or_else(|| parse_error(None, "Could not find btime".to_string()))
+                .and_then(|(n, line)| {
                     let timestamp_str = line
                         .strip_prefix("btime ")
                         .expect("line starts with 'btime '");
                     timestamp_str
                         .parse::<i64>()
-                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
+                        .map_err(|err| parse_error(Some(n + 1), err.to_string()))
                         .and_then(|timestamp| {
-                            OffsetDateTime::from_unix_timestamp(timestamp).map_err(|err| {
-                                io::Error::new(io::ErrorKind::InvalidData, err.to_string())
-                            })
+                            OffsetDateTime::from_unix_timestamp(timestamp)
+                                .map_err(|err| parse_error(Some(n + 1), err.to_string()))
                         })
                 })
         })
@@ -583,23 +2193,22 @@ impl Platform for PlatformImpl {
 
     fn battery_life(&self) -> io::Result<BatteryLife> {
         let dir = "/sys/class/power_supply";
-        let entries = fs::read_dir(&dir)?;
+        let entries = self.files.read_dir(dir)?;
         let mut full = 0;
         let mut now = 0;
         let mut current = 0;
-        for e in entries {
-            let p = e.unwrap().path();
-            let s = p.to_str().unwrap();
-            if value_from_file::<String>(&(s.to_string() + "/type"))
+        for name in entries {
+            let s = format!("{}/{}", dir, name);
+            if self.files.value_from_file::<String>(&(s.clone() + "/type"))
                 .map(|t| t == "Battery")
                 .unwrap_or(false)
             {
-                let f = value_from_file::<i32>(&(s.to_string() + "/energy_full"))
-                    .or_else(|_| value_from_file::<i32>(&(s.to_string() + "/charge_full")));
-                let n = value_from_file::<i32>(&(s.to_string() + "/energy_now"))
-                    .or_else(|_| value_from_file::<i32>(&(s.to_string() + "/charge_now")));
-                let c = value_from_file::<i32>(&(s.to_string() + "/power_now"))
-                    .or_else(|_| value_from_file::<i32>(&(s.to_string() + "/current_now")));
+                let f = self.files.value_from_file::<i32>(&(s.clone() + "/energy_full"))
+                    .or_else(|_| self.files.value_from_file::<i32>(&(s.clone() + "/charge_full")));
+                let n = self.files.value_from_file::<i32>(&(s.clone() + "/energy_now"))
+                    .or_else(|_| self.files.value_from_file::<i32>(&(s.clone() + "/charge_now")));
+                let c = self.files.value_from_file::<i32>(&(s.clone() + "/power_now"))
+                    .or_else(|_| self.files.value_from_file::<i32>(&(s.clone() + "/current_now")));
                 if let (Ok(f), Ok(n), Ok(c)) = (f, n, c) {
                     full += f;
                     now += n;
@@ -614,89 +2223,75 @@ impl Platform for PlatformImpl {
                 remaining_time: time(on_ac, full, now, current),
             })
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Missing battery information",
-            ))
+            Err(Error::NotPresent("battery information".to_string()).into())
         }
     }
 
     fn on_ac_power(&self) -> io::Result<bool> {
         let dir = "/sys/class/power_supply";
-        let entries = fs::read_dir(&dir)?;
+        let entries = self.files.read_dir(dir)?;
         let mut on_ac = false;
-        for e in entries {
-            let p = e.unwrap().path();
-            let s = p.to_str().unwrap();
-            if value_from_file::<String>(&(s.to_string() + "/type"))
+        for name in entries {
+            let s = format!("{}/{}", dir, name);
+            if self.files.value_from_file::<String>(&(s.clone() + "/type"))
                 .map(|t| t == "Mains")
                 .unwrap_or(false)
             {
-                on_ac |= value_from_file::<i32>(&(s.to_string() + "/online")).map(|v| v == 1)?
+                on_ac |= self.files.value_from_file::<i32>(&(s.clone() + "/online")).map(|v| v == 1)?
             }
         }
         Ok(on_ac)
     }
 
     fn mounts(&self) -> io::Result<Vec<Filesystem>> {
-        read_file("/proc/mounts")
-            .and_then(|data| {
-                proc_mounts(&data)
-                    .map(|(_, res)| res)
-                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
-            })
+        self.files.parse_file("/proc/mounts", proc_mounts)
             .map(|mounts| {
                 mounts
                     .into_iter()
-                    .filter_map(|mount| stat_mount(mount).ok())
+                    .filter_map(|mount| stat_mount(&self.files, mount).ok())
                     .collect()
             })
     }
 
-    fn mount_at<P: AsRef<path::Path>>(&self, path: P) -> io::Result<Filesystem> {
-        read_file("/proc/mounts")
-            .and_then(|data| {
-                proc_mounts(&data)
-                    .map(|(_, res)| res)
-                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
-            })
+    fn mount_at_path(&self, path: &path::Path) -> io::Result<Filesystem> {
+        self.files.parse_file("/proc/mounts", proc_mounts)
             .and_then(|mounts| {
                 mounts
                     .into_iter()
-                    .find(|mount| Path::new(&mount.target) == path.as_ref())
-                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No such mount"))
+                    .find(|mount| Path::new(&mount.target) == path)
+                    .ok_or_else(|| Error::NotPresent("mount".to_string()).into())
             })
-            .and_then(stat_mount)
+            .and_then(|mount| stat_mount(&self.files, mount))
     }
 
     fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
-        let mut result: BTreeMap<String, BlockDeviceStats> = BTreeMap::new();
-        let stats: Vec<BlockDeviceStats> = read_file("/proc/diskstats").and_then(|data| {
-            proc_diskstats(&data)
-                .map(|(_, res)| res)
-                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
-        })?;
+        block_device_statistics(&self.files)
+    }
 
-        for blkstats in stats {
-            result.entry(blkstats.name.clone()).or_insert(blkstats);
-        }
-        Ok(result)
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>> {
+        let files = self.files.clone();
+        DelayedMeasurement::rates(move || block_device_statistics(&files))
     }
 
     fn networks(&self) -> io::Result<BTreeMap<String, Network>> {
-        unix::networks()
+        self.files.parse_record(
+            "networks",
+            || unix::networks().map(|networks| networks_record(&networks)),
+            networks_record_s,
+        )
     }
 
+    #[allow(clippy::needless_borrow)]
     fn network_stats(&self, interface: &str) -> io::Result<NetworkStats> {
         let path_root: String = ("/sys/class/net/".to_string() + interface) + "/statistics/";
         let stats_file = |file: &str| (&path_root).to_string() + file;
 
-        let rx_bytes: u64 = value_from_file::<u64>(&stats_file("rx_bytes"))?;
-        let tx_bytes: u64 = value_from_file::<u64>(&stats_file("tx_bytes"))?;
-        let rx_packets: u64 = value_from_file::<u64>(&stats_file("rx_packets"))?;
-        let tx_packets: u64 = value_from_file::<u64>(&stats_file("tx_packets"))?;
-        let rx_errors: u64 = value_from_file::<u64>(&stats_file("rx_errors"))?;
-        let tx_errors: u64 = value_from_file::<u64>(&stats_file("tx_errors"))?;
+        let rx_bytes: u64 = self.files.value_from_file::<u64>(&stats_file("rx_bytes"))?;
+        let tx_bytes: u64 = self.files.value_from_file::<u64>(&stats_file("tx_bytes"))?;
+        let rx_packets: u64 = self.files.value_from_file::<u64>(&stats_file("rx_packets"))?;
+        let tx_packets: u64 = self.files.value_from_file::<u64>(&stats_file("tx_packets"))?;
+        let rx_errors: u64 = self.files.value_from_file::<u64>(&stats_file("rx_errors"))?;
+        let tx_errors: u64 = self.files.value_from_file::<u64>(&stats_file("tx_errors"))?;
 
         Ok(NetworkStats {
             rx_bytes: ByteSize::b(rx_bytes),
@@ -708,30 +2303,37 @@ impl Platform for PlatformImpl {
         })
     }
 
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>> {
+        let mut result = match self.files.read_file("/proc/net/wireless") {
+            Ok(data) => proc_net_wireless(&data).ok_or_else(|| Error::Parse {
+                path: "/proc/net/wireless".to_string(),
+                line: None,
+                message: "Unexpected format".to_string(),
+            })?,
+            // Without wireless extensions in the kernel, sysfs is all there is
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+        for name in self.files.read_dir("/sys/class/net")? {
+            let dir = format!("/sys/class/net/{}/wireless", name);
+            if !result.contains_key(&name) && self.files.is_dir(&dir) {
+                if let Ok(stats) = sysfs_wireless(&self.files, &dir) {
+                    result.insert(name, stats);
+                }
+            }
+        }
+        Ok(result)
+    }
+
     fn cpu_temp(&self) -> io::Result<f32> {
-        read_file("/sys/class/thermal/thermal_zone0/temp")
-            .or(read_file("/sys/class/hwmon/hwmon0/temp1_input"))
-            .and_then(|data| match data.trim().parse::<f32>() {
-                Ok(x) => Ok(x),
-                Err(_) => Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Could not parse float",
-                )),
-            })
+        self.files.value_from_file::<f32>("/sys/class/thermal/thermal_zone0/temp")
+            .or_else(|_| self.files.value_from_file::<f32>("/sys/class/hwmon/hwmon0/temp1_input"))
             .map(|num| num / 1000.0)
     }
 
     fn socket_stats(&self) -> io::Result<SocketStats> {
-        let sockstats: ProcNetSockStat = read_file("/proc/net/sockstat").and_then(|data| {
-            proc_net_sockstat(&data)
-                .map(|(_, res)| res)
-                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
-        })?;
-        let sockstats6: ProcNetSockStat6 = read_file("/proc/net/sockstat6").and_then(|data| {
-            proc_net_sockstat6(&data)
-                .map(|(_, res)| res)
-                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))
-        })?;
+        let sockstats: ProcNetSockStat = self.files.parse_file("/proc/net/sockstat", proc_net_sockstat)?;
+        let sockstats6: ProcNetSockStat6 = self.files.parse_file("/proc/net/sockstat6", proc_net_sockstat6)?;
         let result: SocketStats = SocketStats {
             tcp_sockets_in_use: sockstats.tcp_in_use,
             tcp_sockets_orphaned: sockstats.tcp_orphaned,
@@ -741,13 +2343,177 @@ impl Platform for PlatformImpl {
         };
         Ok(result)
     }
+
+    fn os_info(&self) -> io::Result<OsInfo> {
+        let mut info = self.files.parse_record(
+            "uname",
+            || unix::os_info().map(|info| uname_record(&info)),
+            uname_record_s,
+        )?;
+        info.distribution = distribution(&self.files).ok();
+        info.virtualization = virtualization(&self.files);
+        Ok(info)
+    }
+
+    fn users(&self) -> io::Result<Vec<UserSession>> {
+        self.files.read("/var/run/utmp").map(|data| utmp(&data))
+    }
+
+    fn hardware_info(&self) -> io::Result<HardwareInfo> {
+        dmi_hardware_info(&self.files, "/sys/class/dmi/id")
+    }
+
+    fn kernel_limits(&self) -> io::Result<KernelLimits> {
+        let (file_handles, file_handles_max) = self.files.parse_file("/proc/sys/fs/file-nr", proc_file_nr)?;
+        let (inodes, inodes_free) = self.files.parse_file("/proc/sys/fs/inode-nr", proc_inode_nr)?;
+        Ok(KernelLimits {
+            file_handles,
+            file_handles_max,
+            inodes,
+            inodes_free,
+            pids: self.files.parse_file("/proc/loadavg", proc_loadavg_tasks)?,
+            pid_max: self.files.value_from_file("/proc/sys/kernel/pid_max")?,
+            threads_max: self.files.value_from_file("/proc/sys/kernel/threads-max")?,
+            entropy_avail: self.files.value_from_file("/proc/sys/kernel/random/entropy_avail")?,
+        })
+    }
+
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>> {
+        interrupts(&self.files)
+    }
+
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>> {
+        let files = self.files.clone();
+        DelayedMeasurement::rates(move || interrupts(&files))
+    }
+
+    fn cpu_availability(&self) -> io::Result<CpuAvailability> {
+        Ok(CpuAvailability {
+            online: self.files.parse_file("/sys/devices/system/cpu/online", cpu_list)?,
+            affinity: self.files.parse_record("affinity", sched_affinity, cpu_list)?,
+            quota: cgroup_cpu_quota(&self.files),
+        })
+    }
+
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        let affinity = self.cpu_availability()?.affinity;
+        measure_cpu_load(&self.files, move |cpu| affinity.contains(&cpu))
+    }
+
+    fn processes(&self) -> io::Result<Vec<Process>> {
+        let ticks = clock_ticks(&self.files)?;
+        let users = self.files.read_file("/etc/passwd").map(|data| passwd(&data)).unwrap_or_default();
+        let mut result = Vec::new();
+        for name in self.files.read_dir("/proc")? {
+            if let Ok(pid) = name.parse() {
+                // Skip processes that exit while being read
+                if let Ok(process) = read_process(&self.files, pid, ticks, &users) {
+                    result.push(process);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn threads(&self, pid: u32) -> io::Result<Vec<Thread>> {
+        threads(&self.files, pid)
+    }
+
+    fn thread_load(&self, pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>> {
+        let files = self.files.clone();
+        DelayedMeasurement::rates(move || threads(&files, pid))
+    }
+
+    fn process_fds(&self, pid: u32) -> io::Result<ProcessFds> {
+        let dir = format!("/proc/{}/fd", pid);
+        let mut fds = ProcessFds::default();
+        for name in self.files.read_dir(&dir)? {
+            // Skip descriptors closed in the meantime
+            if let Ok(target) = self.files.read_link(&format!("{}/{}", dir, name)) {
+                count_fd(&mut fds, &target.to_string_lossy());
+            }
+        }
+        Ok(fds)
+    }
+
+    fn process_io(&self, pid: u32) -> io::Result<ProcessIo> {
+        self.files.parse_file(&format!("/proc/{}/io", pid), proc_pid_io)
+    }
+
+    fn process_memory(&self, pid: u32) -> io::Result<ProcessMemory> {
+        self.files
+            .parse_file(&format!("/proc/{}/smaps_rollup", pid), proc_meminfo)
+            .map(|rollup| smaps_rollup_memory(&rollup))
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use Capability::*;
+        let mut caps = Capabilities::new(&[Uptime, Networks, OsInfo]);
+        caps.procfs = self.files.exists("/proc/self/stat");
+        caps.pressure = self.files.is_dir("/proc/pressure");
+        caps.cgroups = cgroup_version(&self.files, "/sys/fs/cgroup");
+        if caps.procfs {
+            caps.supported.extend(&[
+                LoadAverage,
+                Memory,
+                Swap,
+                CpuLoad,
+                BootTime,
+                Mounts,
+                BlockDeviceStatistics,
+                DiskIoRates,
+                SocketStats,
+                KernelLimits,
+                Interrupts,
+                Processes,
+                Threads,
+                ProcessFds,
+            ]);
+        }
+        if self.files.is_dir("/sys/class/net") {
+            caps.supported.insert(NetworkStats);
+        }
+        if has_wireless(&self.files) {
+            caps.supported.insert(Wireless);
+        }
+        if has_power_supply(&self.files, "Battery") {
+            caps.supported.insert(BatteryLife);
+        }
+        if self.files.is_dir("/sys/class/power_supply") {
+            caps.supported.insert(OnAcPower);
+        }
+        if self.files.exists("/sys/class/thermal/thermal_zone0/temp")
+            || self.files.exists("/sys/class/hwmon/hwmon0/temp1_input")
+        {
+            caps.supported.insert(CpuTemp);
+        }
+        if self.files.exists("/var/run/utmp") {
+            caps.supported.insert(Users);
+        }
+        if self.files.is_dir("/sys/class/dmi/id") {
+            caps.supported.insert(HardwareInfo);
+        }
+        if self.files.exists("/proc/self/io") {
+            caps.supported.insert(ProcessIo);
+        }
+        if self.files.exists("/proc/self/smaps_rollup") {
+            caps.supported.insert(ProcessMemory);
+        }
+        if self.files.exists("/sys/devices/system/cpu/online") {
+            caps.supported.insert(CpuAvailability);
+        }
+        caps
+    }
 }
 
 impl PlatformMemory {
     // Retrieve platform memory information
-    fn new() -> io::Result<Self> {
-        memory_stats()
-            .or_else(|_| {
+    fn new(files: &Files) -> io::Result<Self> {
+        memory_stats(files)
+            .or_else(|err| {
+                if files.is_replay() {
+                    return Err(err);
+                }
                 // If there's no procfs, e.g. in a chroot without mounting it or something
                 let mut meminfo = BTreeMap::new();
                 let mut info: sysinfo = unsafe { mem::zeroed() };
@@ -783,6 +2549,7 @@ impl PlatformMemory {
     }
 
     // Convert the platform memory information to Memory
+    #[allow(clippy::wrong_self_convention)]
     fn to_memory(self) -> Memory {
         let meminfo = &self.meminfo;
         Memory {
@@ -802,6 +2569,7 @@ impl PlatformMemory {
     }
 
     // Convert the platform memory information to Swap
+    #[allow(clippy::wrong_self_convention)]
     fn to_swap(self) -> Swap {
         let meminfo = &self.meminfo;
         Swap {
@@ -834,3 +2602,53 @@ struct sysinfo {
 extern "C" {
     fn sysinfo(info: *mut sysinfo);
 }
+
+#[test]
+fn test_capture_replay() {
+    let dir = std::env::temp_dir().join(format!("systemstat-test-capture-{}", std::process::id()));
+    let live = PlatformImpl::capture(&dir);
+    let replay = PlatformImpl::replay(&dir);
+    let show = |sys: &PlatformImpl| {
+        format!(
+            "{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}",
+            sys.load_average(),
+            sys.memory_and_swap(),
+            sys.uptime(),
+            sys.boot_time(),
+            sys.mounts(),
+            sys.block_device_statistics(),
+            sys.networks(),
+            sys.os_info(),
+            sys.kernel_limits(),
+            sys.interrupts(),
+            sys.capabilities(),
+            sys.process_fds(std::process::id()),
+            sys.process_io(std::process::id()),
+            sys.process_memory(std::process::id()),
+            sys.processes(),
+            sys.wireless(),
+        )
+    };
+    let captured = show(&live);
+    assert!(dir.join("proc").is_dir());
+    assert_eq!(show(&replay), captured);
+    let load = replay.cpu_load().unwrap().done().unwrap();
+    assert_eq!(load.len(), live.cpu_load().unwrap().done().unwrap().len());
+    fs::remove_dir_all(&dir).unwrap();
+    // Nothing is read from the running system
+    assert!(replay.memory().is_err());
+    assert!(replay.os_info().is_err());
+}
+
+#[test]
+fn test_capabilities_without_procfs() {
+    let dir = std::env::temp_dir().join(format!("systemstat-test-no-procfs-{}", std::process::id()));
+    save(&dir.join("sys/class/net/eth0/address"), b"00:00:00:00:00:00\n").unwrap();
+    let caps = PlatformImpl::replay(&dir).capabilities();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(!caps.procfs);
+    assert!(caps.supports(Capability::NetworkStats));
+    for &cap in &[Capability::LoadAverage, Capability::Memory, Capability::Swap, Capability::CpuLoad] {
+        assert!(!caps.supports(cap), "{:?}", cap);
+    }
+}
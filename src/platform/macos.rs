@@ -5,10 +5,12 @@ use libc::{
     HOST_VM_INFO64_COUNT, KERN_SUCCESS, VM_SWAPUSAGE, _SC_PHYS_PAGES,
 };
 use crate::data::*;
+use crate::error::Error;
 use super::common::*;
 use super::unix;
 use super::bsd;
 
+#[derive(Default)]
 pub struct PlatformImpl;
 
 macro_rules! sysctl_mib {
@@ -30,7 +32,7 @@ macro_rules! sysctl {
             let mut size = $size;
             if unsafe { sysctl(&mib[0] as *const _ as *mut _, mib.len() as u32,
                                $dataptr as *mut _ as *mut c_void, &mut size, ptr::null_mut(), 0) } != 0 && $shouldcheck {
-                return Err(io::Error::new(io::ErrorKind::Other, "sysctl() failed"))
+                return Err(io::Error::last_os_error())
             }
             size
         }
@@ -53,7 +55,7 @@ impl Platform for PlatformImpl {
     }
 
     fn cpu_load(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn load_average(&self) -> io::Result<LoadAverage> {
@@ -63,12 +65,7 @@ impl Platform for PlatformImpl {
     fn memory(&self) -> io::Result<Memory> {
         // Get Total Memory
         let total = match unsafe { sysconf(_SC_PHYS_PAGES) } {
-            -1 => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "sysconf(_SC_PHYS_PAGES) failed",
-                ))
-            }
+            -1 => return Err(io::Error::last_os_error()),
             n => n as u64,
         };
 
@@ -87,10 +84,7 @@ impl Platform for PlatformImpl {
         };
 
         if ret != KERN_SUCCESS {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "host_statistics64() failed",
-            ));
+            return Err(io::Error::other(format!("host_statistics64() failed: {}", ret)));
         }
         let stat = unsafe { stat.assume_init() };
 
@@ -148,25 +142,29 @@ impl Platform for PlatformImpl {
     }
 
     fn battery_life(&self) -> io::Result<BatteryLife> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn on_ac_power(&self) -> io::Result<bool> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn mounts(&self) -> io::Result<Vec<Filesystem>> {
         let mut mptr: *mut statfs = ptr::null_mut();
         let len = unsafe { getmntinfo(&mut mptr, 2_i32) };
         if len < 1 {
-            return Err(io::Error::new(io::ErrorKind::Other, "getmntinfo() failed"))
+            return Err(io::Error::last_os_error())
         }
         let mounts = unsafe { slice::from_raw_parts(mptr, len as usize) };
         Ok(mounts.iter().map(statfs_to_fs).collect::<Vec<_>>())
     }
 
     fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>> {
+        Err(Error::Unsupported.into())
     }
 
     fn networks(&self) -> io::Result<BTreeMap<String, Network>> {
@@ -174,15 +172,80 @@ impl Platform for PlatformImpl {
     }
 
     fn network_stats(&self, _interface: &str) -> io::Result<NetworkStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>> {
+        Err(Error::Unsupported.into())
     }
 
     fn cpu_temp(&self) -> io::Result<f32> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn socket_stats(&self) -> io::Result<SocketStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn os_info(&self) -> io::Result<OsInfo> {
+        unix::os_info()
+    }
+
+    fn users(&self) -> io::Result<Vec<UserSession>> {
+        unix::users()
+    }
+
+    fn hardware_info(&self) -> io::Result<HardwareInfo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn kernel_limits(&self) -> io::Result<KernelLimits> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_availability(&self) -> io::Result<CpuAvailability> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn processes(&self) -> io::Result<Vec<Process>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn threads(&self, _pid: u32) -> io::Result<Vec<Thread>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn thread_load(&self, _pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_fds(&self, _pid: u32) -> io::Result<ProcessFds> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_io(&self, _pid: u32) -> io::Result<ProcessIo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_memory(&self, _pid: u32) -> io::Result<ProcessMemory> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use Capability::*;
+        Capabilities::new(&[LoadAverage, Memory, Swap, Uptime, BootTime, Mounts, Networks, OsInfo, Users])
     }
 }
 
@@ -48,6 +48,11 @@ pub mod illumos;
 #[cfg(any(target_os = "illumos", target_os = "solaris"))]
 pub use self::illumos::PlatformImpl;
 
+#[cfg(feature = "testing")]
+pub mod fake;
+#[cfg(feature = "testing")]
+pub use self::fake::FakePlatform;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,6 +137,179 @@ mod tests {
         assert!(!networks.values().find(|n| n.name == "lo" || n.name == "lo0").unwrap().addrs.is_empty());
     }
 
+    #[test]
+    fn test_os_info() {
+        let os = PlatformImpl::new().os_info().unwrap();
+        assert!(!os.kernel_name.is_empty() && !os.machine.is_empty());
+    }
+
+    #[test]
+    fn test_cpu_availability() {
+        let sys = PlatformImpl::new();
+        if sys.capabilities().supports(crate::Capability::CpuAvailability) {
+            let cpus = sys.cpu_availability().unwrap();
+            assert!(!cpus.online.is_empty() && !cpus.affinity.is_empty());
+            assert!(cpus.parallelism() <= cpus.affinity.len());
+            let load = sys.cpu_load_affine().unwrap().done().unwrap();
+            assert!(load.len() <= cpus.affinity.len());
+        }
+    }
+
+    #[test]
+    fn test_process_accounting() {
+        let sys = PlatformImpl::new();
+        let pid = std::process::id();
+        let caps = sys.capabilities();
+        if caps.supports(crate::Capability::ProcessFds) {
+            let _file = std::fs::File::open("Cargo.toml").unwrap();
+            let fds = sys.process_fds(pid).unwrap();
+            assert!(fds.files >= 1);
+        }
+        if caps.supports(crate::Capability::ProcessIo) {
+            assert!(sys.process_io(pid).unwrap().read_syscalls > 0);
+        }
+        if caps.supports(crate::Capability::ProcessMemory) {
+            let mem = sys.process_memory(pid).unwrap();
+            assert!(mem.rss >= mem.uss && mem.uss.as_u64() > 0);
+        }
+    }
+
+    #[test]
+    fn test_processes() {
+        let sys = PlatformImpl::new();
+        if sys.capabilities().supports(crate::Capability::Processes) {
+            let pid = std::process::id();
+            let tree = crate::ProcessTree::new(sys.processes().unwrap());
+            let own = tree.get(pid).unwrap();
+            assert!(!own.name.is_empty() && own.ppid != 0);
+            assert!(tree.subtree_usage(own.ppid).processes >= 2);
+            assert!(tree.by_uid().contains_key(&own.uid));
+        }
+    }
+
+    #[test]
+    fn test_threads() {
+        let sys = PlatformImpl::new();
+        if sys.capabilities().supports(crate::Capability::Threads) {
+            let pid = std::process::id();
+            let measurement = sys.thread_load(pid).unwrap();
+            let busy = std::thread::Builder::new()
+                .name("busy-thread".to_string())
+                .spawn(|| {
+                    let started = std::time::Instant::now();
+                    while started.elapsed() < Duration::from_millis(500) {}
+                })
+                .unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            let threads = sys.threads(pid).unwrap();
+            assert!(threads.iter().any(|t| t.tid == pid));
+            let load = measurement.done().unwrap();
+            busy.join().unwrap();
+            assert!(threads.iter().any(|t| t.name == "busy-thread"));
+            assert!(load.iter().any(|t| t.name == "busy-thread" && t.total() > 0.0));
+        }
+    }
+
+    #[test]
+    fn test_wireless() {
+        let sys = PlatformImpl::new();
+        if sys.capabilities().supports(crate::Capability::Wireless) {
+            let networks = sys.networks().unwrap();
+            for (name, stats) in sys.wireless().unwrap() {
+                assert!(networks.contains_key(&name));
+                assert!(!matches!(stats.signal_dbm, Some(dbm) if dbm > 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let caps = PlatformImpl::new().capabilities();
+        assert!(caps.supports(crate::Capability::Networks));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let options = crate::SnapshotOptions {
+            cpu_load_duration: Some(Duration::from_millis(300)),
+            network_stats: true,
+        };
+        let snapshot = PlatformImpl::new().snapshot(&options);
+        assert!(snapshot.networks.unwrap().len() >= snapshot.network_stats.len());
+        if let Some(Ok(load)) = snapshot.cpu_load {
+            assert!(!load.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_delayed_measurement_rates() {
+        use crate::{BTreeMap, DelayedMeasurement};
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+        use std::thread;
+        use std::time::Duration;
+
+        let counter = Arc::new(AtomicU64::new(0));
+        let sampled = counter.clone();
+        let mut measurement = DelayedMeasurement::rates(move || {
+            let mut counters = BTreeMap::new();
+            counters.insert("requests", sampled.load(Ordering::SeqCst));
+            Ok(counters)
+        }).unwrap();
+        counter.store(100, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        assert!(measurement.elapsed() >= Duration::from_millis(50));
+        let rate = measurement.done().unwrap()["requests"];
+        assert!(rate > 0.0 && rate <= 100.0 / 0.05);
+
+        // done() keeps measuring from the start, resample() re-bases
+        let first = measurement.resample().unwrap()["requests"];
+        assert!(first > 0.0);
+        assert!(measurement.elapsed() < Duration::from_millis(50));
+        assert_eq!(measurement.done().unwrap()["requests"], 0.0);
+        counter.store(150, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(10));
+        let rate = measurement.resample().unwrap()["requests"];
+        assert!(rate > 0.0 && rate <= 50.0 / 0.01);
+    }
+
+    #[test]
+    fn test_disk_statistics() {
+        let sys = PlatformImpl::new();
+        if sys.capabilities().supports(crate::Capability::BlockDeviceStatistics) {
+            let all = sys.block_device_statistics().unwrap();
+            let disks = sys.disk_statistics().unwrap();
+            assert!(disks.len() <= all.len());
+            assert!(disks.values().all(|disk| disk.kind.is_physical_disk()));
+            for device in all.values().filter(|device| device.kind == crate::BlockDeviceKind::Partition) {
+                if let Some(parent) = &device.parent {
+                    assert!(all.contains_key(parent));
+                }
+            }
+            let total = sys.disk_statistics_aggregate().unwrap();
+            assert!(total.read_ios >= disks.values().map(|disk| disk.read_ios).max().unwrap_or(0));
+        }
+    }
+
+    #[test]
+    fn test_disk_io_rates() {
+        let sys = PlatformImpl::new();
+        if sys.capabilities().supports(crate::Capability::DiskIoRates) {
+            let measurement = sys.disk_io_rates().unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            let rates = measurement.done().unwrap();
+            assert!(rates.values().all(|disk| disk.utilization <= 100.0));
+        }
+    }
+
+    #[test]
+    fn test_trait_object() {
+        let sys: Box<dyn Platform> = Box::new(PlatformImpl::new());
+        if sys.capabilities().supports(crate::Capability::Mounts) {
+            assert_eq!(sys.mount_at_path(std::path::Path::new("/")).unwrap().fs_mounted_on, "/");
+        }
+    }
+
     #[test]
     fn test_cpu_measurement_is_send() {
         use crate::{DelayedMeasurement, CPULoad};
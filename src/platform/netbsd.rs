@@ -2,9 +2,11 @@
 use super::common::*;
 use super::unix;
 use crate::data::*;
+use crate::error::Error;
 use libc::{c_int, c_void, sysctl, CTL_VM};
 use std::{io, mem, path, ptr};
 
+#[derive(Default)]
 pub struct PlatformImpl;
 
 // https://github.com/NetBSD/src/blob/8e2e7cb174ca27b848b18119f33cf4c212fe22ee/sys/uvm/uvm_param.h#L169
@@ -26,7 +28,7 @@ macro_rules! sysctl {
         } != 0
             && $shouldcheck
         {
-            return Err(io::Error::new(io::ErrorKind::Other, "sysctl() failed"));
+            return Err(io::Error::last_os_error());
         }
         size
     }};
@@ -44,7 +46,7 @@ impl Platform for PlatformImpl {
     }
 
     fn cpu_load(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn load_average(&self) -> io::Result<LoadAverage> {
@@ -65,27 +67,31 @@ impl Platform for PlatformImpl {
     }
 
     fn boot_time(&self) -> io::Result<OffsetDateTime> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn battery_life(&self) -> io::Result<BatteryLife> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn on_ac_power(&self) -> io::Result<bool> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn mounts(&self) -> io::Result<Vec<Filesystem>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
-    fn mount_at<P: AsRef<path::Path>>(&self, _: P) -> io::Result<Filesystem> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+    fn mount_at_path(&self, _path: &path::Path) -> io::Result<Filesystem> {
+        Err(Error::Unsupported.into())
     }
 
     fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>> {
+        Err(Error::Unsupported.into())
     }
 
     fn networks(&self) -> io::Result<BTreeMap<String, Network>> {
@@ -93,15 +99,80 @@ impl Platform for PlatformImpl {
     }
 
     fn network_stats(&self, _interface: &str) -> io::Result<NetworkStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>> {
+        Err(Error::Unsupported.into())
     }
 
     fn cpu_temp(&self) -> io::Result<f32> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn socket_stats(&self) -> io::Result<SocketStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn os_info(&self) -> io::Result<OsInfo> {
+        unix::os_info()
+    }
+
+    fn users(&self) -> io::Result<Vec<UserSession>> {
+        unix::users()
+    }
+
+    fn hardware_info(&self) -> io::Result<HardwareInfo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn kernel_limits(&self) -> io::Result<KernelLimits> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_availability(&self) -> io::Result<CpuAvailability> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn processes(&self) -> io::Result<Vec<Process>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn threads(&self, _pid: u32) -> io::Result<Vec<Thread>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn thread_load(&self, _pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_fds(&self, _pid: u32) -> io::Result<ProcessFds> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_io(&self, _pid: u32) -> io::Result<ProcessIo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_memory(&self, _pid: u32) -> io::Result<ProcessMemory> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use Capability::*;
+        Capabilities::new(&[LoadAverage, Memory, Swap, Networks, OsInfo, Users])
     }
 }
 
@@ -131,6 +202,7 @@ impl PlatformMemory {
             swonly: ByteSize::b((uvm_info.swpgonly << uvm_info.pageshift) as u64),
         })
     }
+    #[allow(clippy::wrong_self_convention)]
     fn to_memory(self) -> Memory {
         Memory {
             total: self.total,
@@ -138,6 +210,7 @@ impl PlatformMemory {
             platform_memory: self,
         }
     }
+    #[allow(clippy::wrong_self_convention)]
     fn to_swap(self) -> Swap {
         Swap {
             total: self.sw,
@@ -4,10 +4,12 @@ use std::os::unix::ffi::OsStrExt;
 use std::mem::size_of;
 use libc::{c_void, c_int, c_uint, c_ulong, c_uchar, ioctl, sysctl, timeval, statfs, ifaddrs, getifaddrs, if_data, freeifaddrs};
 use crate::data::*;
+use crate::error::Error;
 use super::common::*;
 use super::unix;
 use super::bsd;
 
+#[derive(Default)]
 pub struct PlatformImpl;
 
 macro_rules! sysctl {
@@ -17,7 +19,7 @@ macro_rules! sysctl {
             let mut size = $size;
             if unsafe { sysctl(&mib[0], mib.len() as u32,
                                $dataptr as *mut _ as *mut c_void, &mut size, ptr::null_mut(), 0) } != 0 && $shouldcheck {
-                return Err(io::Error::new(io::ErrorKind::Other, "sysctl() failed"))
+                return Err(io::Error::last_os_error())
             }
             size
         }
@@ -88,13 +90,13 @@ impl Platform for PlatformImpl {
         let f = fs::File::open("/dev/apm")?;
         let mut info = apm_power_info::default();
         if unsafe { ioctl(f.as_raw_fd(), *APM_IOC_GETPOWER, &mut info) } == -1 {
-            return Err(io::Error::new(io::ErrorKind::Other, "ioctl() failed"))
+            return Err(io::Error::last_os_error())
         }
         if info.battery_state == 0xff { // APM_BATT_UNKNOWN
-            return Err(io::Error::new(io::ErrorKind::Other, "Battery state unknown"))
+            return Err(Error::NotPresent("battery state".to_string()).into())
         }
         if info.battery_state == 4 { // APM_BATTERY_ABSENT
-            return Err(io::Error::new(io::ErrorKind::Other, "Battery absent"))
+            return Err(Error::NotPresent("battery".to_string()).into())
         }
         Ok(BatteryLife {
             remaining_capacity: info.battery_life as f32,
@@ -106,7 +108,7 @@ impl Platform for PlatformImpl {
         let f = fs::File::open("/dev/apm")?;
         let mut info = apm_power_info::default();
         if unsafe { ioctl(f.as_raw_fd(), *APM_IOC_GETPOWER, &mut info) } == -1 {
-            return Err(io::Error::new(io::ErrorKind::Other, "ioctl() failed"))
+            return Err(io::Error::last_os_error())
         }
         Ok(info.ac_state == 0x01) // APM_AC_ON
     }
@@ -115,23 +117,27 @@ impl Platform for PlatformImpl {
         let mut mptr: *mut statfs = ptr::null_mut();
         let len = unsafe { getmntinfo(&mut mptr, 1 as i32) };
         if len < 1 {
-            return Err(io::Error::new(io::ErrorKind::Other, "getmntinfo() failed"))
+            return Err(io::Error::last_os_error())
         }
         let mounts = unsafe { slice::from_raw_parts(mptr, len as usize) };
         Ok(mounts.iter().map(|m| statfs_to_fs(&m)).collect::<Vec<_>>())
     }
 
-    fn mount_at<P: AsRef<path::Path>>(&self, path: P) -> io::Result<Filesystem> {
-        let path = ffi::CString::new(path.as_ref().as_os_str().as_bytes())?;
+    fn mount_at_path(&self, path: &path::Path) -> io::Result<Filesystem> {
+        let path = ffi::CString::new(path.as_os_str().as_bytes())?;
         let mut sfs: statfs = unsafe { mem::zeroed() };
         if unsafe { statfs(path.as_ptr() as *const _, &mut sfs) } != 0 {
-            return Err(io::Error::new(io::ErrorKind::Other, "statfs() failed"));
+            return Err(io::Error::last_os_error());
         }
         Ok(statfs_to_fs(&sfs))
     }
 
     fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>> {
+        Err(Error::Unsupported.into())
     }
 
     fn networks(&self) -> io::Result<BTreeMap<String, Network>> {
@@ -157,7 +163,7 @@ impl Platform for PlatformImpl {
                 let c_str: &std::ffi::CStr = std::ffi::CStr::from_ptr((*ifa).ifa_name);
                 let str_net: &str = match c_str.to_str() {
                     Ok(v)  => v,
-                    Err(_) => return Err(io::Error::new(io::ErrorKind::Other, "C string cannot be converted"))
+                    Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, "C string cannot be converted"))
                 };
                 if interface == str_net {
                     data        = (*ifa).ifa_data as *mut if_data;
@@ -185,12 +191,77 @@ impl Platform for PlatformImpl {
         })
     }
 
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>> {
+        Err(Error::Unsupported.into())
+    }
+
     fn cpu_temp(&self) -> io::Result<f32> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn socket_stats(&self) -> io::Result<SocketStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn os_info(&self) -> io::Result<OsInfo> {
+        unix::os_info()
+    }
+
+    fn users(&self) -> io::Result<Vec<UserSession>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn hardware_info(&self) -> io::Result<HardwareInfo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn kernel_limits(&self) -> io::Result<KernelLimits> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_availability(&self) -> io::Result<CpuAvailability> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn processes(&self) -> io::Result<Vec<Process>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn threads(&self, _pid: u32) -> io::Result<Vec<Thread>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn thread_load(&self, _pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_fds(&self, _pid: u32) -> io::Result<ProcessFds> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_io(&self, _pid: u32) -> io::Result<ProcessIo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_memory(&self, _pid: u32) -> io::Result<ProcessMemory> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use Capability::*;
+        Capabilities::new(&[CpuLoad, LoadAverage, Memory, Swap, Uptime, BootTime, BatteryLife, OnAcPower, Mounts, Networks, NetworkStats, OsInfo])
     }
 }
 
@@ -232,6 +303,7 @@ impl PlatformMemory {
             swonly: ByteSize::kib((uvm_info.swpgonly << *bsd::PAGESHIFT) as u64),
         })
     }
+    #[allow(clippy::wrong_self_convention)]
     fn to_memory(self) -> Memory {
         Memory {
             total: self.total,
@@ -239,6 +311,7 @@ impl PlatformMemory {
             platform_memory: self,
         }
     }
+    #[allow(clippy::wrong_self_convention)]
     fn to_swap(self) -> Swap {
         Swap {
             total: self.sw,
@@ -1,11 +1,13 @@
 use std::{io, ffi, ptr, mem};
-use libc::{c_int, getifaddrs, freeifaddrs, ifaddrs, sockaddr, sockaddr_in6, AF_INET, AF_INET6};
+#[cfg(not(target_os = "openbsd"))]
+use std::convert::TryFrom;
+use libc::{c_char, c_int, getifaddrs, freeifaddrs, ifaddrs, sockaddr, sockaddr_in6, uname, utsname, AF_INET, AF_INET6};
 use crate::data::*;
 
 pub fn load_average() -> io::Result<LoadAverage> {
     let mut loads: [f64; 3] = [0.0, 0.0, 0.0];
     if unsafe { getloadavg(&mut loads[0], 3) } != 3 {
-        return Err(io::Error::new(io::ErrorKind::Other, "getloadavg() failed"))
+        return Err(io::Error::last_os_error())
     }
     Ok(LoadAverage {
         one: loads[0] as f32,
@@ -17,7 +19,7 @@ pub fn load_average() -> io::Result<LoadAverage> {
 pub fn networks() -> io::Result<BTreeMap<String, Network>> {
     let mut ifap: *mut ifaddrs = ptr::null_mut();
     if unsafe { getifaddrs(&mut ifap) } != 0 {
-        return Err(io::Error::new(io::ErrorKind::Other, "getifaddrs() failed"))
+        return Err(io::Error::last_os_error())
     }
     let ifirst = ifap;
     let mut result = BTreeMap::new();
@@ -61,6 +63,83 @@ fn parse_addr(aptr: *const sockaddr) -> IpAddr {
     }
 }
 
+pub fn os_info() -> io::Result<OsInfo> {
+    let mut uts: utsname = unsafe { mem::zeroed() };
+    if unsafe { uname(&mut uts) } < 0 {
+        return Err(io::Error::last_os_error())
+    }
+    let field = |f: &[c_char]| unsafe { ffi::CStr::from_ptr(f.as_ptr()).to_string_lossy().into_owned() };
+    Ok(OsInfo {
+        hostname: field(&uts.nodename),
+        kernel_name: field(&uts.sysname),
+        kernel_release: field(&uts.release),
+        kernel_version: field(&uts.version),
+        machine: field(&uts.machine),
+        distribution: None,
+        virtualization: None,
+    })
+}
+
+/// A record of the utmpx database (Android only has `utmp`, which is the same,
+/// and OpenBSD has neither)
+#[cfg(target_os = "android")]
+pub use libc::utmp as utmpx;
+#[cfg(not(any(target_os = "android", target_os = "openbsd")))]
+pub use libc::utmpx;
+
+// Read a NUL-padded string field of a utmpx record
+#[cfg(not(target_os = "openbsd"))]
+fn utmpx_str(field: &[c_char]) -> String {
+    let bytes = field.iter().map(|&c| c as u8).take_while(|&c| c != 0).collect::<Vec<_>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Converts a utmpx record, returning `None` if it's not a user login
+/// or has a negative PID or login time.
+#[cfg(not(target_os = "openbsd"))]
+pub fn utmpx_session(ut: &utmpx) -> Option<UserSession> {
+    #[cfg(target_os = "netbsd")]
+    let username = utmpx_str(&ut.ut_name);
+    #[cfg(not(target_os = "netbsd"))]
+    let username = utmpx_str(&ut.ut_user);
+    if ut.ut_type != libc::USER_PROCESS || username.is_empty() {
+        return None;
+    }
+    let secs = i64::from(ut.ut_tv.tv_sec);
+    let micros = u64::try_from(ut.ut_tv.tv_usec).ok()?;
+    if secs < 0 {
+        return None;
+    }
+    Some(UserSession {
+        username,
+        tty: utmpx_str(&ut.ut_line),
+        host: utmpx_str(&ut.ut_host),
+        login_time: OffsetDateTime::from_unix_timestamp(secs).ok()? + Duration::from_micros(micros),
+        pid: u32::try_from(ut.ut_pid).ok()?,
+    })
+}
+
+/// Reads the user logins from the system's utmpx database.
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "openbsd")))]
+pub fn users() -> io::Result<Vec<UserSession>> {
+    // The position in the database is global state
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let _guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut result = Vec::new();
+    unsafe {
+        libc::setutxent();
+        loop {
+            let ut = libc::getutxent();
+            if ut.is_null() {
+                break;
+            }
+            result.extend(utmpx_session(&*ut));
+        }
+        libc::endutxent();
+    }
+    Ok(result)
+}
+
 #[link(name = "c")]
 extern "C" {
     fn getloadavg(loadavg: *mut f64, nelem: c_int) -> c_int;
@@ -1,7 +1,7 @@
 use winapi::ctypes::c_char;
 use winapi::shared::minwindef::*;
 use winapi::shared::winerror::ERROR_SUCCESS;
-use winapi::um::{sysinfoapi, winbase};
+use winapi::um::{sysinfoapi, winbase, winnt};
 use winapi::um::pdh::{
     PDH_FMT_COUNTERVALUE_ITEM_A,
     PDH_FMT_DOUBLE,
@@ -21,6 +21,7 @@ mod socket;
 
 use super::common::*;
 use crate::data::*;
+use crate::error::Error;
 
 use std::ffi::CStr;
 use std::slice::from_raw_parts;
@@ -52,6 +53,7 @@ fn last_os_error() -> io::Result<()> {
     Err(io::Error::last_os_error())
 }
 
+#[derive(Default)]
 pub struct PlatformImpl;
 
 /// An implementation of `Platform` for Windows.
@@ -228,7 +230,7 @@ impl Platform for PlatformImpl {
     }
 
     fn load_average(&self) -> io::Result<LoadAverage> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn memory(&self) -> io::Result<Memory> {
@@ -252,13 +254,10 @@ impl Platform for PlatformImpl {
     fn battery_life(&self) -> io::Result<BatteryLife> {
         let status = power_status();
         if status.BatteryFlag == 128 {
-            return Err(io::Error::new(io::ErrorKind::Other, "Battery absent"));
+            return Err(Error::NotPresent("battery".to_string()).into());
         }
         if status.BatteryFlag == 255 {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Battery status unknown",
-            ));
+            return Err(Error::NotPresent("battery status".to_string()).into());
         }
         Ok(BatteryLife {
             remaining_capacity: status.BatteryLifePercent as f32 / 100.0,
@@ -275,7 +274,11 @@ impl Platform for PlatformImpl {
     }
 
     fn block_device_statistics(&self) -> io::Result<BTreeMap<String, BlockDeviceStats>> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn disk_io_rates(&self) -> io::Result<DelayedMeasurement<BTreeMap<String, DiskIoRates>>> {
+        Err(Error::Unsupported.into())
     }
 
     fn networks(&self) -> io::Result<BTreeMap<String, Network>> {
@@ -283,16 +286,117 @@ impl Platform for PlatformImpl {
     }
 
     fn network_stats(&self, _interface: &str) -> io::Result<NetworkStats> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
+    }
+
+    fn wireless(&self) -> io::Result<BTreeMap<String, WirelessStats>> {
+        Err(Error::Unsupported.into())
     }
 
     fn cpu_temp(&self) -> io::Result<f32> {
-        Err(io::Error::new(io::ErrorKind::Other, "Not supported"))
+        Err(Error::Unsupported.into())
     }
 
     fn socket_stats(&self) -> io::Result<SocketStats> {
         socket::get()
     }
+
+    fn os_info(&self) -> io::Result<OsInfo> {
+        let mut name = [0u16; 256];
+        let mut size = name.len() as DWORD;
+        if unsafe { sysinfoapi::GetComputerNameExW(sysinfoapi::ComputerNameDnsHostname, name.as_mut_ptr(), &mut size) } == 0 {
+            last_os_error()?;
+        }
+
+        let mut version: winnt::OSVERSIONINFOW = unsafe { mem::zeroed() };
+        version.dwOSVersionInfoSize = mem::size_of::<winnt::OSVERSIONINFOW>() as DWORD;
+        // Unlike GetVersionExW, this is not affected by the application's compatibility manifest
+        if unsafe { RtlGetVersion(&mut version) } != 0 {
+            return Err(io::Error::other("RtlGetVersion() failed"));
+        }
+
+        let mut system: sysinfoapi::SYSTEM_INFO = unsafe { mem::zeroed() };
+        unsafe { sysinfoapi::GetNativeSystemInfo(&mut system) };
+        let machine = match unsafe { system.u.s().wProcessorArchitecture } {
+            winnt::PROCESSOR_ARCHITECTURE_AMD64 => "x86_64",
+            winnt::PROCESSOR_ARCHITECTURE_ARM64 => "aarch64",
+            winnt::PROCESSOR_ARCHITECTURE_ARM => "arm",
+            winnt::PROCESSOR_ARCHITECTURE_INTEL => "x86",
+            _ => "unknown",
+        };
+
+        Ok(OsInfo {
+            hostname: u16_array_to_string(name.as_ptr()),
+            kernel_name: "Windows".to_string(),
+            kernel_release: format!("{}.{}.{}", version.dwMajorVersion, version.dwMinorVersion, version.dwBuildNumber),
+            kernel_version: u16_array_to_string(version.szCSDVersion.as_ptr()),
+            machine: machine.to_string(),
+            distribution: None,
+            virtualization: None,
+        })
+    }
+
+    fn users(&self) -> io::Result<Vec<UserSession>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn hardware_info(&self) -> io::Result<HardwareInfo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn kernel_limits(&self) -> io::Result<KernelLimits> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupts(&self) -> io::Result<Vec<Interrupt>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn interrupt_rates(&self) -> io::Result<DelayedMeasurement<Vec<InterruptRate>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_availability(&self) -> io::Result<CpuAvailability> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn cpu_load_affine(&self) -> io::Result<DelayedMeasurement<Vec<CPULoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn processes(&self) -> io::Result<Vec<Process>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn threads(&self, _pid: u32) -> io::Result<Vec<Thread>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn thread_load(&self, _pid: u32) -> io::Result<DelayedMeasurement<Vec<ThreadLoad>>> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_fds(&self, _pid: u32) -> io::Result<ProcessFds> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_io(&self, _pid: u32) -> io::Result<ProcessIo> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn process_memory(&self, _pid: u32) -> io::Result<ProcessMemory> {
+        Err(Error::Unsupported.into())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        use Capability::*;
+        Capabilities::new(&[CpuLoad, Memory, Swap, Uptime, BootTime, BatteryLife, OnAcPower, Mounts, Networks, SocketStats, OsInfo])
+    }
+}
+
+#[link(name = "ntdll")]
+extern "system" {
+    fn RtlGetVersion(info: *mut winnt::OSVERSIONINFOW) -> i32;
 }
 
 fn power_status() -> winbase::SYSTEM_POWER_STATUS {
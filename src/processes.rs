@@ -0,0 +1,254 @@
+//! This module provides rollups of the resources used by processes: over the subtree of
+//! a process (e.g. a service and its workers), per user and per control group.
+//!
+//! The CPU usage needs two lists of processes taken some time apart, so `ProcessTree`
+//! implements `Counters`:
+//!
+//! ```no_run
+//! use std::{thread, time::Duration};
+//! use systemstat::{DelayedMeasurement, Platform, System};
+//!
+//! let sys = System::new();
+//! let measurement = DelayedMeasurement::rates(move || sys.processes()).unwrap();
+//! thread::sleep(Duration::from_secs(1));
+//! let tree = measurement.done().unwrap();
+//! for (user, usage) in tree.by_user() {
+//!     println!("{}: {:.1} CPUs, {} in {} processes", user, usage.cpu, usage.rss, usage.processes);
+//! }
+//! ```
+
+use crate::data::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Resources used by a group of processes.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "the_serde")
+)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceUsage {
+    /// Number of processes
+    pub processes: usize,
+    /// CPU time used since the processes started
+    pub cpu_time: Duration,
+    /// CPU used over the measurement, in CPUs (e.g. 1.5 is one and a half CPUs fully busy).
+    /// Always 0 for a `ProcessTree` made with `new` rather than measured.
+    pub cpu: f32,
+    /// Sum of the resident set sizes, which counts shared memory more than once
+    pub rss: ByteSize,
+}
+
+impl ResourceUsage {
+    fn add(&mut self, process: &Process, cpu: f32) {
+        self.processes += 1;
+        self.cpu_time += process.cpu_time();
+        self.cpu += cpu;
+        self.rss += process.rss;
+    }
+}
+
+/// Processes arranged by their parent process IDs.
+#[derive(Debug, Clone)]
+pub struct ProcessTree {
+    processes: BTreeMap<u32, Process>,
+    children: BTreeMap<u32, Vec<u32>>,
+    cpu: BTreeMap<u32, f32>,
+}
+
+impl ProcessTree {
+    pub fn new(processes: Vec<Process>) -> ProcessTree {
+        let mut children = BTreeMap::new();
+        for process in &processes {
+            children.entry(process.ppid).or_insert_with(Vec::new).push(process.pid);
+        }
+        ProcessTree {
+            processes: processes.into_iter().map(|process| (process.pid, process)).collect(),
+            children,
+            cpu: BTreeMap::new(),
+        }
+    }
+
+    /// Builds the tree of `next`, with the CPU usage since `prev` was taken `elapsed` before.
+    ///
+    /// Processes are paired up by ID and name. Processes that only appear in `next`
+    /// started in the meantime, so all their CPU time counts.
+    pub fn between(prev: &[Process], next: Vec<Process>, elapsed: Duration) -> ProcessTree {
+        let secs = elapsed.as_secs_f32();
+        let prev: BTreeMap<u32, &Process> = prev.iter().map(|process| (process.pid, process)).collect();
+        let cpu = next
+            .iter()
+            .map(|process| {
+                let before = match prev.get(&process.pid) {
+                    Some(prev) if prev.name == process.name => prev.cpu_time(),
+                    _ => Duration::ZERO,
+                };
+                let used = process.cpu_time().saturating_sub(before).as_secs_f32();
+                (process.pid, if secs > 0.0 { used / secs } else { 0.0 })
+            })
+            .collect();
+        ProcessTree { cpu, ..ProcessTree::new(next) }
+    }
+
+    pub fn get(&self, pid: u32) -> Option<&Process> {
+        self.processes.get(&pid)
+    }
+
+    /// All the processes, ordered by ID
+    pub fn processes(&self) -> impl Iterator<Item = &Process> {
+        self.processes.values()
+    }
+
+    /// CPU used by a process over the measurement, in CPUs
+    pub fn cpu(&self, pid: u32) -> f32 {
+        self.cpu.get(&pid).copied().unwrap_or(0.0)
+    }
+
+    /// The direct children of a process
+    pub fn children(&self, pid: u32) -> impl Iterator<Item = &Process> {
+        self.children
+            .get(&pid)
+            .into_iter()
+            .flatten()
+            .filter_map(move |child| self.processes.get(child))
+    }
+
+    /// Processes whose parent isn't in the tree, e.g. init and kthreadd on Linux
+    pub fn roots(&self) -> impl Iterator<Item = &Process> {
+        self.processes
+            .values()
+            .filter(move |process| !self.processes.contains_key(&process.ppid) || process.ppid == process.pid)
+    }
+
+    /// A process and all its descendants
+    pub fn subtree(&self, pid: u32) -> Vec<&Process> {
+        let mut result = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![pid];
+        while let Some(pid) = stack.pop() {
+            // Guard against loops, which can show up when PIDs are reused
+            if !seen.insert(pid) {
+                continue;
+            }
+            if let Some(process) = self.processes.get(&pid) {
+                result.push(process);
+                stack.extend(self.children.get(&pid).into_iter().flatten().rev());
+            }
+        }
+        result
+    }
+
+    /// The resources used by a process and all its descendants
+    pub fn subtree_usage(&self, pid: u32) -> ResourceUsage {
+        self.usage_by(self.subtree(pid), |_| ()).remove(&()).unwrap_or_default()
+    }
+
+    /// The resources used by each user's processes, by user ID
+    pub fn by_uid(&self) -> BTreeMap<u32, ResourceUsage> {
+        self.usage_by(self.processes.values(), |process| process.uid)
+    }
+
+    /// The resources used by each user's processes, by username
+    /// (or user ID, for users that aren't in the user database)
+    pub fn by_user(&self) -> BTreeMap<String, ResourceUsage> {
+        self.usage_by(self.processes.values(), |process| {
+            process.username.clone().unwrap_or_else(|| process.uid.to_string())
+        })
+    }
+
+    /// The resources used by the processes in each control group, by path
+    /// (processes without a known control group are under the empty path)
+    pub fn by_cgroup(&self) -> BTreeMap<String, ResourceUsage> {
+        self.usage_by(self.processes.values(), |process| process.cgroup.clone().unwrap_or_default())
+    }
+
+    fn usage_by<'a, K: Ord>(
+        &self,
+        processes: impl IntoIterator<Item = &'a Process>,
+        key: impl Fn(&Process) -> K,
+    ) -> BTreeMap<K, ResourceUsage> {
+        let mut result = BTreeMap::new();
+        for process in processes {
+            result
+                .entry(key(process))
+                .or_insert_with(ResourceUsage::default)
+                .add(process, self.cpu(process.pid));
+        }
+        result
+    }
+}
+
+impl Counters for Vec<Process> {
+    type Rates = ProcessTree;
+
+    fn rates(prev: &Vec<Process>, next: &Vec<Process>, elapsed: Duration) -> ProcessTree {
+        ProcessTree::between(prev, next.clone(), elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, ppid: u32, uid: u32, cgroup: &str, cpu_secs: u64, rss_mib: u64) -> Process {
+        Process {
+            pid,
+            ppid,
+            name: format!("proc{}", pid),
+            state: ProcessState::Sleeping,
+            uid,
+            username: if uid == 0 { Some("root".to_string()) } else { None },
+            cgroup: Some(cgroup.to_string()),
+            user_time: Duration::from_secs(cpu_secs),
+            system_time: Duration::from_secs(0),
+            rss: ByteSize::mib(rss_mib),
+        }
+    }
+
+    fn processes(extra_cpu: u64) -> Vec<Process> {
+        vec![
+            process(1, 0, 0, "/init.scope", 10, 10),
+            process(100, 1, 33, "/system.slice/nginx.service", 5, 20),
+            process(101, 100, 33, "/system.slice/nginx.service", 20 + extra_cpu, 30),
+            process(102, 100, 33, "/system.slice/nginx.service", 20, 30),
+            process(200, 1, 1000, "/user.slice", 1, 5),
+        ]
+    }
+
+    #[test]
+    fn test_tree() {
+        let tree = ProcessTree::new(processes(0));
+        assert_eq!(tree.roots().map(|p| p.pid).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(tree.children(1).map(|p| p.pid).collect::<Vec<_>>(), vec![100, 200]);
+        assert_eq!(tree.subtree(100).iter().map(|p| p.pid).collect::<Vec<_>>(), vec![100, 101, 102]);
+        let nginx = tree.subtree_usage(100);
+        assert_eq!(nginx.processes, 3);
+        assert_eq!(nginx.cpu_time, Duration::from_secs(45));
+        assert_eq!(nginx.rss, ByteSize::mib(80));
+        assert_eq!(nginx.cpu, 0.0);
+        assert_eq!(tree.subtree_usage(1).processes, 5);
+        assert_eq!(tree.subtree_usage(999), ResourceUsage::default());
+    }
+
+    #[test]
+    fn test_aggregation() {
+        let tree = ProcessTree::between(&processes(0), processes(3), Duration::from_secs(2));
+        assert_eq!(tree.cpu(101), 1.5);
+        assert_eq!(tree.cpu(102), 0.0);
+        let users = tree.by_user();
+        assert_eq!(users.keys().collect::<Vec<_>>(), vec!["1000", "33", "root"]);
+        assert_eq!(users["33"].cpu, 1.5);
+        assert_eq!(tree.by_uid()[&0].rss, ByteSize::mib(10));
+        let cgroups = tree.by_cgroup();
+        assert_eq!(cgroups["/system.slice/nginx.service"].processes, 3);
+        assert_eq!(cgroups["/system.slice/nginx.service"].cpu_time, Duration::from_secs(48));
+
+        // A process that started in the meantime, reusing a PID
+        let mut next = processes(0);
+        next[4].name = "new".to_string();
+        let tree = Counters::rates(&processes(0), &next, Duration::from_secs(2));
+        assert_eq!(tree.cpu(200), 0.5);
+    }
+}
@@ -0,0 +1,730 @@
+//! This module provides a stable, platform-neutral serialized form of a `Snapshot`.
+//!
+//! The data types in `data` derive `Serialize`/`Deserialize` directly, so their serialized form
+//! differs between platforms (e.g. `PlatformMemory`) and depends on how upstream crates
+//! serialize `Duration`, `ByteSize` and `OffsetDateTime`. The types here don't:
+//!
+//! - every document has a `version` field, currently `1`, which is incremented whenever the
+//!   format changes incompatibly (documents with an unknown version are rejected);
+//! - sizes are integers in bytes (`*_bytes`), durations are integers in seconds (`*_secs`),
+//!   points in time are integers in milliseconds since the Unix epoch (`*_ms`);
+//! - platform-specific details are left out, or reduced to a common form
+//!   (e.g. `other` in `CpuLoad` is the sum of the platform-specific CPU states);
+//! - IP addresses are strings, enums are lower-case strings;
+//! - floating point values that aren't finite (e.g. a NaN CPU load) are `null`;
+//! - every metric is either `{"value": ...}` or `{"error": {"kind": ..., "message": ...}}`.
+//!
+//! Converting is one-way, from `data::Snapshot` to `schema::Snapshot` (or from the individual
+//! `data` types to their counterparts here, e.g. `schema::Memory::from(&memory)`):
+//!
+//! ```
+//! use systemstat::{Platform, System, SnapshotOptions, schema};
+//!
+//! let sys = System::new();
+//! let snapshot = sys.snapshot(&SnapshotOptions { cpu_load_duration: None, ..SnapshotOptions::default() });
+//! let stable = schema::Snapshot::from(&snapshot);
+//! assert_eq!(stable.version, schema::VERSION);
+//! ```
+
+use crate::data;
+use crate::data::{BTreeMap, OffsetDateTime, SnapshotErrorKind, SnapshotResult};
+use serde::de::{Deserializer, Error as _};
+use serde::{Deserialize, Serialize};
+
+/// The current version of the format.
+pub const VERSION: u32 = 1;
+
+fn version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let version = u32::deserialize(deserializer)?;
+    if version == 0 || version > VERSION {
+        return Err(D::Error::custom(format!("unsupported schema version {}", version)));
+    }
+    Ok(version)
+}
+
+/// Converts a point in time to the form used by the `*_ms` fields.
+pub fn millis(time: &OffsetDateTime) -> i64 {
+    (time.unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+// serde_json writes NaN and infinities as `null`, which then fails to deserialize into a float
+fn finite(x: f32) -> Option<f32> {
+    if x.is_finite() { Some(x) } else { None }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "the_serde", rename_all = "snake_case")]
+pub enum ErrorKind {
+    Unsupported,
+    NotPresent,
+    Parse,
+    Io,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+/// A metric that was either collected or not.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde", rename_all = "snake_case")]
+pub enum Metric<T> {
+    Value(T),
+    Error(Error),
+}
+
+impl<T> Metric<T> {
+    /// Converts the value with `f`, or keeps the error.
+    pub fn from_result<U, F: FnOnce(&U) -> T>(result: &SnapshotResult<U>, f: F) -> Metric<T> {
+        match result {
+            Ok(value) => Metric::Value(f(value)),
+            Err(err) => Metric::Error(Error {
+                kind: match err.kind {
+                    SnapshotErrorKind::Unsupported => ErrorKind::Unsupported,
+                    SnapshotErrorKind::NotPresent => ErrorKind::NotPresent,
+                    SnapshotErrorKind::Parse => ErrorKind::Parse,
+                    SnapshotErrorKind::Io => ErrorKind::Io,
+                },
+                message: err.message.clone(),
+            }),
+        }
+    }
+
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Metric::Value(value) => Some(value),
+            Metric::Error(_) => None,
+        }
+    }
+}
+
+/// Fractions of time (0 to 1) spent in each state.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct CpuLoad {
+    pub user: Option<f32>,
+    pub nice: Option<f32>,
+    pub system: Option<f32>,
+    pub interrupt: Option<f32>,
+    pub idle: Option<f32>,
+    /// All platform-specific states (e.g. iowait on Linux) combined
+    pub other: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct LoadAverage {
+    pub one: Option<f32>,
+    pub five: Option<f32>,
+    pub fifteen: Option<f32>,
+}
+
+/// Used for both memory and swap.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct Memory {
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct BatteryLife {
+    pub remaining_capacity: Option<f32>,
+    pub remaining_time_secs: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct Filesystem {
+    pub mounted_from: String,
+    pub mounted_on: String,
+    pub fs_type: String,
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    pub avail_bytes: u64,
+    pub files: u64,
+    pub files_total: u64,
+    pub files_avail: u64,
+    pub name_max: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(crate = "the_serde", rename_all = "snake_case")]
+pub enum BlockDeviceKind {
+    Disk,
+    Partition,
+    DeviceMapper,
+    Md,
+    Loop,
+    Ram,
+    Zram,
+    NvmeNamespace,
+    Optical,
+    /// Not written by this version, or missing from older documents
+    #[serde(other)]
+    #[default]
+    Unknown,
+}
+
+/// Counters as found in `/proc/diskstats`, times are in milliseconds, sectors are 512 bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct BlockDeviceStats {
+    #[serde(default)]
+    pub kind: BlockDeviceKind,
+    /// For partitions, the name of the device they're on
+    #[serde(default)]
+    pub parent: Option<String>,
+    pub read_ios: u64,
+    pub read_merges: u64,
+    pub read_sectors: u64,
+    pub read_ticks: u64,
+    pub write_ios: u64,
+    pub write_merges: u64,
+    pub write_sectors: u64,
+    pub write_ticks: u64,
+    pub in_flight: u64,
+    pub io_ticks: u64,
+    pub time_in_queue: u64,
+}
+
+/// Addresses are `None` when missing or not IPv4/IPv6.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct NetworkAddrs {
+    pub addr: Option<String>,
+    pub netmask: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct NetworkStats {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct SocketStats {
+    pub tcp_sockets_in_use: u64,
+    pub tcp_sockets_orphaned: u64,
+    pub udp_sockets_in_use: u64,
+    pub tcp6_sockets_in_use: u64,
+    pub udp6_sockets_in_use: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct Distribution {
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub version_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct OsInfo {
+    pub hostname: String,
+    pub kernel_name: String,
+    pub kernel_release: String,
+    pub kernel_version: String,
+    pub machine: String,
+    pub distribution: Option<Distribution>,
+    /// e.g. `docker`, `kvm`, `hyperv`, or another name for less common environments
+    pub virtualization: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct UserSession {
+    pub username: String,
+    pub tty: String,
+    pub host: String,
+    pub login_time_ms: i64,
+    pub pid: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct HardwareInfo {
+    pub system_vendor: Option<String>,
+    pub product_name: Option<String>,
+    pub serial: Option<String>,
+    pub uuid: Option<String>,
+    pub board_vendor: Option<String>,
+    pub board_name: Option<String>,
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    pub bios_date: Option<String>,
+    /// The SMBIOS chassis type code
+    pub chassis_type: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct KernelLimits {
+    pub file_handles: u64,
+    pub file_handles_max: u64,
+    pub inodes: u64,
+    pub inodes_free: u64,
+    pub pids: u64,
+    pub pid_max: u64,
+    pub threads_max: u64,
+    pub entropy_avail: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct Interrupt {
+    /// `hardware` or `soft`
+    pub kind: String,
+    pub irq: String,
+    pub per_cpu: Vec<u64>,
+    pub description: String,
+}
+
+/// The stable form of `data::Snapshot`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "the_serde")]
+pub struct Snapshot {
+    #[serde(deserialize_with = "version")]
+    pub version: u32,
+    pub timestamp_ms: i64,
+    pub cpu_load: Option<Metric<Vec<CpuLoad>>>,
+    pub load_average: Metric<LoadAverage>,
+    pub memory: Metric<Memory>,
+    pub swap: Metric<Memory>,
+    pub uptime_secs: Metric<u64>,
+    pub boot_time_ms: Metric<i64>,
+    pub battery_life: Metric<BatteryLife>,
+    pub on_ac_power: Metric<bool>,
+    pub mounts: Metric<Vec<Filesystem>>,
+    /// By device name
+    pub block_device_statistics: Metric<BTreeMap<String, BlockDeviceStats>>,
+    /// Addresses by interface name
+    pub networks: Metric<BTreeMap<String, Vec<NetworkAddrs>>>,
+    /// By interface name
+    pub network_stats: BTreeMap<String, Metric<NetworkStats>>,
+    pub cpu_temp: Metric<Option<f32>>,
+    pub socket_stats: Metric<SocketStats>,
+    pub os_info: Metric<OsInfo>,
+    pub users: Metric<Vec<UserSession>>,
+    pub hardware_info: Metric<HardwareInfo>,
+    pub kernel_limits: Metric<KernelLimits>,
+    pub interrupts: Metric<Vec<Interrupt>>,
+}
+
+fn ip_string(addr: &data::IpAddr) -> Option<String> {
+    match addr {
+        data::IpAddr::V4(ip) => Some(ip.to_string()),
+        data::IpAddr::V6(ip) => Some(ip.to_string()),
+        data::IpAddr::Empty | data::IpAddr::Unsupported => None,
+    }
+}
+
+fn virtualization_name(virt: &data::Virtualization) -> String {
+    use crate::data::Virtualization::*;
+    match virt {
+        Docker => "docker",
+        Podman => "podman",
+        Lxc => "lxc",
+        Kubernetes => "kubernetes",
+        Kvm => "kvm",
+        Qemu => "qemu",
+        VMware => "vmware",
+        VirtualBox => "virtualbox",
+        Xen => "xen",
+        HyperV => "hyperv",
+        Other(name) => name,
+    }
+    .to_string()
+}
+
+impl From<&data::CPULoad> for CpuLoad {
+    fn from(cpu: &data::CPULoad) -> CpuLoad {
+        CpuLoad {
+            user: finite(cpu.user),
+            nice: finite(cpu.nice),
+            system: finite(cpu.system),
+            interrupt: finite(cpu.interrupt),
+            idle: finite(cpu.idle),
+            other: finite(cpu.platform.sum()),
+        }
+    }
+}
+
+impl From<&data::Memory> for Memory {
+    fn from(m: &data::Memory) -> Memory {
+        Memory { total_bytes: m.total.as_u64(), free_bytes: m.free.as_u64() }
+    }
+}
+
+impl From<&data::Swap> for Memory {
+    fn from(m: &data::Swap) -> Memory {
+        Memory { total_bytes: m.total.as_u64(), free_bytes: m.free.as_u64() }
+    }
+}
+
+impl From<&data::BatteryLife> for BatteryLife {
+    fn from(b: &data::BatteryLife) -> BatteryLife {
+        BatteryLife {
+            remaining_capacity: finite(b.remaining_capacity),
+            remaining_time_secs: b.remaining_time.as_secs(),
+        }
+    }
+}
+
+impl From<&data::Filesystem> for Filesystem {
+    fn from(fs: &data::Filesystem) -> Filesystem {
+        Filesystem {
+            mounted_from: fs.fs_mounted_from.clone(),
+            mounted_on: fs.fs_mounted_on.clone(),
+            fs_type: fs.fs_type.clone(),
+            total_bytes: fs.total.as_u64(),
+            free_bytes: fs.free.as_u64(),
+            avail_bytes: fs.avail.as_u64(),
+            files: fs.files as u64,
+            files_total: fs.files_total as u64,
+            files_avail: fs.files_avail as u64,
+            name_max: fs.name_max as u64,
+        }
+    }
+}
+
+impl From<&data::NetworkAddrs> for NetworkAddrs {
+    fn from(a: &data::NetworkAddrs) -> NetworkAddrs {
+        NetworkAddrs { addr: ip_string(&a.addr), netmask: ip_string(&a.netmask) }
+    }
+}
+
+impl From<&data::NetworkStats> for NetworkStats {
+    fn from(n: &data::NetworkStats) -> NetworkStats {
+        NetworkStats {
+            rx_bytes: n.rx_bytes.as_u64(),
+            tx_bytes: n.tx_bytes.as_u64(),
+            rx_packets: n.rx_packets,
+            tx_packets: n.tx_packets,
+            rx_errors: n.rx_errors,
+            tx_errors: n.tx_errors,
+        }
+    }
+}
+
+impl From<&data::Snapshot> for Snapshot {
+    fn from(s: &data::Snapshot) -> Snapshot {
+        Snapshot {
+            version: VERSION,
+            timestamp_ms: millis(&s.timestamp),
+            cpu_load: s.cpu_load.as_ref().map(|cpus| Metric::from_result(cpus, |cpus| cpus.iter().map(CpuLoad::from).collect())),
+            load_average: Metric::from_result(&s.load_average, |l| LoadAverage {
+                one: finite(l.one),
+                five: finite(l.five),
+                fifteen: finite(l.fifteen),
+            }),
+            memory: Metric::from_result(&s.memory, |m| m.into()),
+            swap: Metric::from_result(&s.swap, |m| m.into()),
+            uptime_secs: Metric::from_result(&s.uptime, |d| d.as_secs()),
+            boot_time_ms: Metric::from_result(&s.boot_time, millis),
+            battery_life: Metric::from_result(&s.battery_life, |b| b.into()),
+            on_ac_power: Metric::from_result(&s.on_ac_power, |x| *x),
+            mounts: Metric::from_result(&s.mounts, |mounts| mounts.iter().map(Filesystem::from).collect()),
+            block_device_statistics: Metric::from_result(&s.block_device_statistics, |disks| {
+                disks.iter()
+                    .map(|(name, d)| (name.clone(), BlockDeviceStats {
+                        kind: match d.kind {
+                            data::BlockDeviceKind::Disk => BlockDeviceKind::Disk,
+                            data::BlockDeviceKind::Partition => BlockDeviceKind::Partition,
+                            data::BlockDeviceKind::DeviceMapper => BlockDeviceKind::DeviceMapper,
+                            data::BlockDeviceKind::Md => BlockDeviceKind::Md,
+                            data::BlockDeviceKind::Loop => BlockDeviceKind::Loop,
+                            data::BlockDeviceKind::Ram => BlockDeviceKind::Ram,
+                            data::BlockDeviceKind::Zram => BlockDeviceKind::Zram,
+                            data::BlockDeviceKind::NvmeNamespace => BlockDeviceKind::NvmeNamespace,
+                            data::BlockDeviceKind::Optical => BlockDeviceKind::Optical,
+                        },
+                        parent: d.parent.clone(),
+                        read_ios: d.read_ios as u64,
+                        read_merges: d.read_merges as u64,
+                        read_sectors: d.read_sectors as u64,
+                        read_ticks: d.read_ticks as u64,
+                        write_ios: d.write_ios as u64,
+                        write_merges: d.write_merges as u64,
+                        write_sectors: d.write_sectors as u64,
+                        write_ticks: d.write_ticks as u64,
+                        in_flight: d.in_flight as u64,
+                        io_ticks: d.io_ticks as u64,
+                        time_in_queue: d.time_in_queue as u64,
+                    }))
+                    .collect()
+            }),
+            networks: Metric::from_result(&s.networks, |networks| {
+                networks.iter()
+                    .map(|(name, n)| (name.clone(), n.addrs.iter().map(NetworkAddrs::from).collect()))
+                    .collect()
+            }),
+            network_stats: s.network_stats.iter()
+                .map(|(name, stats)| (name.clone(), Metric::from_result(stats, |n| n.into())))
+                .collect(),
+            cpu_temp: Metric::from_result(&s.cpu_temp, |t| finite(*t)),
+            socket_stats: Metric::from_result(&s.socket_stats, |x| SocketStats {
+                tcp_sockets_in_use: x.tcp_sockets_in_use as u64,
+                tcp_sockets_orphaned: x.tcp_sockets_orphaned as u64,
+                udp_sockets_in_use: x.udp_sockets_in_use as u64,
+                tcp6_sockets_in_use: x.tcp6_sockets_in_use as u64,
+                udp6_sockets_in_use: x.udp6_sockets_in_use as u64,
+            }),
+            os_info: Metric::from_result(&s.os_info, |x| OsInfo {
+                hostname: x.hostname.clone(),
+                kernel_name: x.kernel_name.clone(),
+                kernel_release: x.kernel_release.clone(),
+                kernel_version: x.kernel_version.clone(),
+                machine: x.machine.clone(),
+                distribution: x.distribution.as_ref().map(|d| Distribution {
+                    id: d.id.clone(),
+                    name: d.name.clone(),
+                    version: d.version.clone(),
+                    version_id: d.version_id.clone(),
+                }),
+                virtualization: x.virtualization.as_ref().map(virtualization_name),
+            }),
+            users: Metric::from_result(&s.users, |users| {
+                users.iter()
+                    .map(|u| UserSession {
+                        username: u.username.clone(),
+                        tty: u.tty.clone(),
+                        host: u.host.clone(),
+                        login_time_ms: millis(&u.login_time),
+                        pid: u.pid,
+                    })
+                    .collect()
+            }),
+            hardware_info: Metric::from_result(&s.hardware_info, |x| HardwareInfo {
+                system_vendor: x.system_vendor.clone(),
+                product_name: x.product_name.clone(),
+                serial: x.serial.clone(),
+                uuid: x.uuid.clone(),
+                board_vendor: x.board_vendor.clone(),
+                board_name: x.board_name.clone(),
+                bios_vendor: x.bios_vendor.clone(),
+                bios_version: x.bios_version.clone(),
+                bios_date: x.bios_date.clone(),
+                chassis_type: x.chassis_type.map(|c| c.to_smbios()),
+            }),
+            kernel_limits: Metric::from_result(&s.kernel_limits, |x| KernelLimits {
+                file_handles: x.file_handles,
+                file_handles_max: x.file_handles_max,
+                inodes: x.inodes,
+                inodes_free: x.inodes_free,
+                pids: x.pids,
+                pid_max: x.pid_max,
+                threads_max: x.threads_max,
+                entropy_avail: x.entropy_avail,
+            }),
+            interrupts: Metric::from_result(&s.interrupts, |irqs| {
+                irqs.iter()
+                    .map(|i| Interrupt {
+                        kind: match i.kind {
+                            data::InterruptKind::Hardware => "hardware",
+                            data::InterruptKind::Soft => "soft",
+                        }
+                        .to_string(),
+                        irq: i.irq.clone(),
+                        per_cpu: i.per_cpu.clone(),
+                        description: i.description.clone(),
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{ChassisType, Duration, IpAddr, Ipv4Addr, Network, PlatformCpuLoad, Virtualization};
+    use serde::de::DeserializeOwned;
+    use std::fmt::Debug;
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T) -> T {
+        let json = serde_json::to_string(value).unwrap();
+        let result = serde_json::from_str::<T>(&json).unwrap();
+        assert_eq!(&result, value, "{}", json);
+        result
+    }
+
+    #[test]
+    fn test_from_snapshot() {
+        let mut snapshot = crate::encode::test_snapshot();
+        snapshot.os_info = Ok(data::OsInfo {
+            hostname: "web1".to_string(),
+            kernel_name: "Linux".to_string(),
+            kernel_release: "6.1.0".to_string(),
+            kernel_version: "#1 SMP".to_string(),
+            machine: "x86_64".to_string(),
+            distribution: None,
+            virtualization: Some(Virtualization::HyperV),
+        });
+        snapshot.users = Ok(vec![data::UserSession {
+            username: "val".to_string(),
+            tty: "pts/0".to_string(),
+            host: "10.0.0.1".to_string(),
+            login_time: OffsetDateTime::from_unix_timestamp(1699999000).unwrap() + Duration::from_millis(250),
+            pid: 1234,
+        }]);
+        snapshot.networks = Ok(vec![("eth0".to_string(), Network {
+            name: "eth0".to_string(),
+            addrs: vec![data::NetworkAddrs {
+                addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+                netmask: IpAddr::Empty,
+            }],
+        })].into_iter().collect());
+        let json = serde_json::to_value(Snapshot::from(&snapshot)).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["timestamp_ms"], 1700000000000i64);
+        assert_eq!(json["cpu_load"]["value"][1]["user"], 0.5);
+        assert_eq!(json["memory"]["error"]["kind"], "unsupported");
+        assert_eq!(json["battery_life"]["error"]["kind"], "not_present");
+        assert_eq!(json["uptime_secs"]["value"], 3600);
+        assert_eq!(json["boot_time_ms"]["value"], 1699996400000i64);
+        assert_eq!(json["mounts"]["value"][0]["total_bytes"], 1048576);
+        assert_eq!(json["block_device_statistics"]["value"]["sda"]["read_sectors"], 800);
+        assert_eq!(json["block_device_statistics"]["value"]["sda"]["kind"], "disk");
+        assert_eq!(json["networks"]["value"]["eth0"][0]["addr"], "10.0.0.2");
+        assert_eq!(json["networks"]["value"]["eth0"][0]["netmask"], serde_json::Value::Null);
+        assert_eq!(json["network_stats"]["eth0"]["value"]["rx_bytes"], 1000);
+        assert_eq!(json["os_info"]["value"]["virtualization"], "hyperv");
+        assert_eq!(json["users"]["value"][0]["login_time_ms"], 1699999000250i64);
+        assert_eq!(json["interrupts"]["value"][0]["kind"], "hardware");
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut snapshot = Snapshot::from(&crate::encode::test_snapshot());
+        snapshot.memory = Metric::Value(Memory { total_bytes: 8 << 30, free_bytes: 1 << 30 });
+        snapshot.battery_life = Metric::Value(BatteryLife { remaining_capacity: Some(0.75), remaining_time_secs: 5400 });
+        snapshot.os_info = Metric::Value(OsInfo {
+            hostname: "mac".to_string(),
+            kernel_name: "Darwin".to_string(),
+            kernel_release: "23.1.0".to_string(),
+            kernel_version: "Darwin Kernel Version 23.1.0".to_string(),
+            machine: "arm64".to_string(),
+            distribution: Some(Distribution {
+                id: "macos".to_string(),
+                name: "macOS".to_string(),
+                version: None,
+                version_id: Some("14.1".to_string()),
+            }),
+            virtualization: None,
+        });
+        snapshot.hardware_info = Metric::Value(HardwareInfo {
+            system_vendor: Some("Apple".to_string()),
+            product_name: None,
+            serial: None,
+            uuid: None,
+            board_vendor: None,
+            board_name: None,
+            bios_vendor: None,
+            bios_version: None,
+            bios_date: None,
+            chassis_type: Some(ChassisType::Laptop.to_smbios()),
+        });
+        snapshot.kernel_limits = Metric::Value(KernelLimits {
+            file_handles: 1024,
+            file_handles_max: 65536,
+            inodes: 100,
+            inodes_free: 10,
+            pids: 300,
+            pid_max: 4194304,
+            threads_max: 60000,
+            entropy_avail: 256,
+        });
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<Snapshot>(&json).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_cpu_load_round_trip() {
+        let mut snapshot = crate::encode::test_snapshot();
+        snapshot.cpu_load = Some(Ok(vec![data::CPULoad {
+            user: f32::NAN,
+            nice: 0.0,
+            system: f32::INFINITY,
+            interrupt: 0.0,
+            idle: 0.75,
+            platform: PlatformCpuLoad::zero(),
+        }]));
+        let stable = Snapshot::from(&snapshot);
+        let cpus = round_trip(stable.cpu_load.as_ref().unwrap().value().unwrap());
+        assert_eq!(cpus[0].user, None);
+        assert_eq!(cpus[0].system, None);
+        assert_eq!(cpus[0].idle, Some(0.75));
+    }
+
+    #[test]
+    fn test_load_average_round_trip() {
+        let mut snapshot = crate::encode::test_snapshot();
+        snapshot.load_average = Ok(data::LoadAverage { one: f32::NAN, five: 0.25, fifteen: f32::NEG_INFINITY });
+        let stable = Snapshot::from(&snapshot);
+        let load = round_trip(stable.load_average.value().unwrap());
+        assert_eq!(load, LoadAverage { one: None, five: Some(0.25), fifteen: None });
+    }
+
+    #[test]
+    fn test_battery_life_round_trip() {
+        let mut snapshot = crate::encode::test_snapshot();
+        snapshot.battery_life = Ok(data::BatteryLife { remaining_capacity: f32::NAN, remaining_time: Duration::from_secs(60) });
+        let stable = Snapshot::from(&snapshot);
+        let battery = round_trip(stable.battery_life.value().unwrap());
+        assert_eq!(battery, BatteryLife { remaining_capacity: None, remaining_time_secs: 60 });
+    }
+
+    #[test]
+    fn test_cpu_temp_round_trip() {
+        let mut snapshot = crate::encode::test_snapshot();
+        assert_eq!(round_trip(&Snapshot::from(&snapshot).cpu_temp), Metric::Value(Some(45.5)));
+        snapshot.cpu_temp = Ok(f32::NAN);
+        assert_eq!(round_trip(&Snapshot::from(&snapshot).cpu_temp), Metric::Value(None));
+    }
+
+    #[test]
+    fn test_non_finite_round_trip() {
+        let mut snapshot = crate::encode::test_snapshot();
+        snapshot.load_average = Ok(data::LoadAverage { one: f32::NAN, five: f32::NAN, fifteen: f32::NAN });
+        snapshot.cpu_temp = Ok(f32::INFINITY);
+        round_trip(&Snapshot::from(&snapshot));
+    }
+
+    #[test]
+    fn test_block_device_kind() {
+        let kind = |json| serde_json::from_value::<BlockDeviceStats>(json).unwrap().kind;
+        let mut json = serde_json::to_value(&Snapshot::from(&crate::encode::test_snapshot()).block_device_statistics).unwrap();
+        let sda = json["value"]["sda"].take();
+        assert_eq!(kind(sda.clone()), BlockDeviceKind::Disk);
+        let mut optical = sda.clone();
+        optical["kind"] = "optical".into();
+        assert_eq!(kind(optical), BlockDeviceKind::Optical);
+        let mut newer = sda.clone();
+        newer["kind"] = "tape".into();
+        assert_eq!(kind(newer), BlockDeviceKind::Unknown);
+        let mut older = sda;
+        older.as_object_mut().unwrap().remove("kind");
+        assert_eq!(kind(older), BlockDeviceKind::Unknown);
+    }
+
+    #[test]
+    fn test_unknown_version() {
+        let mut json = serde_json::to_value(Snapshot::from(&crate::encode::test_snapshot())).unwrap();
+        json["version"] = serde_json::Value::from(VERSION + 1);
+        let err = serde_json::from_value::<Snapshot>(json).unwrap_err();
+        assert!(err.to_string().contains("unsupported schema version 2"));
+    }
+}
@@ -0,0 +1,216 @@
+//! This module provides a way to get periodic updates without managing timers or measurements.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use systemstat::Watcher;
+//!
+//! let watcher = Watcher::new(Duration::from_secs(1)).unwrap();
+//! for update in watcher.take(5) {
+//!     println!("{:?}", update.memory);
+//! }
+//! ```
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use std::io;
+use crate::data::*;
+use crate::platform::{Platform, PlatformImpl};
+
+/// One round of measurements taken by a `Watcher`.
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub timestamp: OffsetDateTime,
+    /// Time since the previous update (or since the watcher was started),
+    /// which the CPU load and the rates are measured over
+    pub elapsed: Duration,
+    pub cpu_load: SnapshotResult<Vec<CPULoad>>,
+    pub memory: SnapshotResult<Memory>,
+    pub swap: SnapshotResult<Swap>,
+    /// By interface name
+    pub network_rates: SnapshotResult<BTreeMap<String, NetworkRates>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// The latest update that hasn't been received yet
+    latest: Option<Update>,
+    /// Set by the watcher to stop the background thread
+    stopped: bool,
+    /// Set when the background thread has exited
+    finished: bool,
+}
+
+type Shared = Arc<(Mutex<State>, Condvar)>;
+
+fn lock(shared: &Shared) -> MutexGuard<'_, State> {
+    shared.0.lock().expect("lock should not be poisoned")
+}
+
+/// Marks the background thread as finished when it exits, even by panicking,
+/// so that receiving doesn't block forever.
+struct Finished(Shared);
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        let Finished(shared) = self;
+        lock(shared).finished = true;
+        shared.1.notify_all();
+    }
+}
+
+/// Measures CPU load, memory and network rates on a background thread, every `interval`.
+///
+/// Updates are received by iterating over the watcher (which blocks until the next one)
+/// or with `try_next`. Only the latest update is kept: if the receiver falls behind,
+/// older updates are replaced rather than queued.
+/// The background thread stops when the watcher is dropped.
+pub struct Watcher {
+    shared: Shared,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    /// Starts watching the current system.
+    pub fn new(interval: Duration) -> io::Result<Watcher> {
+        Watcher::with_platform(PlatformImpl::new(), interval)
+    }
+
+    /// Starts watching using any `Platform` implementation.
+    pub fn with_platform<P: Platform + Send + 'static>(platform: P, interval: Duration) -> io::Result<Watcher> {
+        let shared: Shared = Arc::new((Mutex::new(State::default()), Condvar::new()));
+        let thread_shared = shared.clone();
+        let thread = thread::Builder::new()
+            .name("systemstat-watcher".to_string())
+            .spawn(move || {
+                let _finished = Finished(thread_shared.clone());
+                watch(platform, interval, &thread_shared)
+            })?;
+        Ok(Watcher { shared, thread: Some(thread) })
+    }
+
+    /// Returns the latest update if there's one waiting, without blocking.
+    pub fn try_next(&self) -> Option<Update> {
+        lock(&self.shared).latest.take()
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = Update;
+
+    /// Blocks until the next update.
+    fn next(&mut self) -> Option<Update> {
+        let mut state = lock(&self.shared);
+        loop {
+            if let Some(update) = state.latest.take() {
+                return Some(update);
+            }
+            if state.finished {
+                return None;
+            }
+            state = self.shared.1.wait(state).expect("lock should not be poisoned");
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        lock(&self.shared).stopped = true;
+        self.shared.1.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn network_stats<P: Platform>(platform: &P) -> SnapshotResult<BTreeMap<String, NetworkStats>> {
+    Ok(platform
+        .networks()?
+        .keys()
+        .filter_map(|name| platform.network_stats(name).ok().map(|stats| (name.clone(), stats)))
+        .collect())
+}
+
+fn watch<P: Platform>(platform: P, interval: Duration, shared: &Shared) {
+    let mut cpu_load = platform.cpu_load().map_err(SnapshotError::from);
+    let mut net = network_stats(&platform);
+    let mut last = Instant::now();
+    let mut deadline = last;
+    loop {
+        // Wait until the next tick, relative to the start to avoid drifting
+        deadline += interval;
+        if deadline < Instant::now() {
+            // After a slow round, start over from now rather than catching up with a burst of updates
+            deadline = Instant::now();
+        }
+        {
+            let mut state = lock(shared);
+            while !state.stopped {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                state = shared.1.wait_timeout(state, deadline - now).expect("lock should not be poisoned").0;
+            }
+            if state.stopped {
+                return;
+            }
+        }
+
+        let now = Instant::now();
+        let elapsed = now - last;
+        last = now;
+        let next_net = network_stats(&platform);
+        let update = Update {
+            timestamp: OffsetDateTime::now_utc(),
+            elapsed,
+            cpu_load: match &mut cpu_load {
+                Ok(measurement) => measurement.resample().map_err(SnapshotError::from),
+                Err(err) => Err(err.clone()),
+            },
+            memory: platform.memory().map_err(SnapshotError::from),
+            swap: platform.swap().map_err(SnapshotError::from),
+            network_rates: match (&net, &next_net) {
+                (Ok(prev), Ok(next)) => Ok(Counters::rates(prev, next, elapsed)),
+                (Err(err), _) | (_, Err(err)) => Err(err.clone()),
+            },
+        };
+        net = next_net;
+
+        lock(shared).latest = Some(update);
+        shared.1.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watcher() {
+        let started = Instant::now();
+        let mut watcher = Watcher::new(Duration::from_millis(50)).unwrap();
+        let first = watcher.next().unwrap();
+        let second = watcher.next().unwrap();
+        assert!(first.elapsed >= Duration::from_millis(40));
+        assert!(second.elapsed >= Duration::from_millis(40));
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        if PlatformImpl::new().capabilities().supports(Capability::CpuLoad) {
+            assert!(!second.cpu_load.unwrap().is_empty());
+        }
+        // Dropping stops the thread promptly instead of waiting for the next tick
+        let watcher = Watcher::new(Duration::from_secs(3600)).unwrap();
+        let dropping = Instant::now();
+        drop(watcher);
+        assert!(dropping.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_latest_update() {
+        let watcher = Watcher::new(Duration::from_millis(20)).unwrap();
+        thread::sleep(Duration::from_millis(400));
+        // The receiver fell behind: the waiting update is a recent one, not the first
+        let update = watcher.try_next().unwrap();
+        assert!(OffsetDateTime::now_utc() - update.timestamp < Duration::from_millis(200));
+    }
+}